@@ -0,0 +1,428 @@
+use crate::bitboard::BitBoard;
+use crate::board::{Board, Move};
+use crate::cli::Options;
+use crate::heuristic::Weights;
+use crate::merge::{Classic, MergeRule};
+use crate::replay::Replay;
+use crate::topology::Topology;
+use crate::strategy::{Expectimax, Settings};
+use failure::{bail, format_err, Error};
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::sync::Arc;
+
+pub const USAGE: &str = "usage: tui-2048 analyze --replay FILE [--depth N] [--weights FILE] \
+[--format text|json] [--output FILE]";
+
+const OPTIONS: &[&str] = &["replay", "depth", "weights", "format", "output", "help"];
+
+/// Losses of expected value up to which a move is still called good or an
+/// inaccuracy, worth about 7 and 37 empty blocks with the default weights.
+/// Anything worse, including any real risk of losing the game, is a blunder.
+const GOOD_LOSS: f64 = 2_000.0;
+const INACCURACY_LOSS: f64 = 10_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Best,
+    Good,
+    Inaccuracy,
+    Blunder,
+}
+
+impl Verdict {
+    fn from_loss(loss: f64) -> Verdict {
+        if loss <= 0.0 {
+            Verdict::Best
+        } else if loss <= GOOD_LOSS {
+            Verdict::Good
+        } else if loss <= INACCURACY_LOSS {
+            Verdict::Inaccuracy
+        } else {
+            Verdict::Blunder
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Verdict::Best => "best",
+            Verdict::Good => "good",
+            Verdict::Inaccuracy => "inaccuracy",
+            Verdict::Blunder => "blunder",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// How one move of a game compares to the engine's choice.
+#[derive(Debug, Clone)]
+pub struct MoveAnalysis {
+    /// The board before the move.
+    pub board: Board,
+    pub played: Move,
+    pub best: Move,
+    /// Expected value of every legal move, in the order of `Move::ALL`.
+    pub values: Vec<(Move, f64)>,
+    /// Value lost by the played move, as a fraction of the best value, at
+    /// most 1.
+    pub loss: f64,
+    pub verdict: Verdict,
+}
+
+/// What a single direction does to a position.
+#[derive(Debug, Clone)]
+pub struct DirectionAnalysis {
+    pub direction: Move,
+    /// Whether the move changes the board.
+    pub legal: bool,
+    /// Points scored by the move's merges.
+    pub score: u64,
+    /// Empty blocks after the move, before a new block is put.
+    pub empty: usize,
+    /// Expectimax value of the move; `None` when it is illegal or the board
+    /// is not 4x4.
+    pub value: Option<f64>,
+    /// The board after the move, before a new block is put.
+    pub board: Board,
+}
+
+/// Looks at each of the four directions from `board`.
+pub fn directions(board: &Board, engine: &mut Expectimax) -> Vec<DirectionAnalysis> {
+    let values = engine.move_values(board);
+
+    Move::ALL
+        .iter()
+        .map(|&direction| {
+            let next = board.try_to_move(direction);
+
+            DirectionAnalysis {
+                direction,
+                legal: next.updated,
+                score: next.score,
+                empty: next.empty_indexes().len(),
+                value: values
+                    .iter()
+                    .find(|&&(other, _)| other == direction)
+                    .map(|&(_, value)| value),
+                board: next,
+            }
+        })
+        .collect()
+}
+
+pub struct Report {
+    pub moves: Vec<MoveAnalysis>,
+    /// The board after the last move.
+    pub final_board: Board,
+}
+
+/// Runs every move of a 4x4 replay of the classic rules through expectimax.
+pub fn analyze(replay: &Replay, settings: &Settings) -> Result<Report, Error> {
+    if replay.width != 4 || replay.height != 4 {
+        bail!("only 4x4 games can be analyzed");
+    }
+    if replay.merge_rule.name() != Classic.name() {
+        bail!("only games of the classic rules can be analyzed");
+    }
+    if replay.topology != Topology::Grid || replay.layout.is_some() || replay.specials > 0.0 {
+        bail!("only games on plain grids without special tiles can be analyzed");
+    }
+
+    let mut positions = replay.positions()?;
+    let final_board = positions.pop().unwrap();
+    let mut engine = Expectimax::new(settings.depth, Arc::clone(&settings.heuristic));
+
+    let moves = positions
+        .into_iter()
+        .zip(&replay.moves)
+        .enumerate()
+        .map(|(i, (board, &played))| {
+            // the engine has no values for boards it cannot pack
+            if let Err(e) = BitBoard::try_from(&board) {
+                bail!("move {}: {}", i + 1, e);
+            }
+
+            let values = engine.move_values(&board);
+            let value_of = |direction| {
+                values
+                    .iter()
+                    .find(|&&(other, _)| other == direction)
+                    .map(|&(_, value)| value)
+                    .unwrap_or(0.0)
+            };
+            let best = values.iter().fold(played, |best, &(direction, value)| {
+                if value > value_of(best) {
+                    direction
+                } else {
+                    best
+                }
+            });
+            // a difference rather than a ratio, so that it still holds when
+            // every move may lose the game and the best value is negative
+            let loss = value_of(best) - value_of(played);
+
+            Ok(MoveAnalysis {
+                board,
+                played,
+                best,
+                values,
+                loss,
+                verdict: Verdict::from_loss(loss),
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+
+    Ok(Report { moves, final_board })
+}
+
+impl Report {
+    pub fn count(&self, verdict: Verdict) -> usize {
+        self.moves
+            .iter()
+            .filter(|analysis| analysis.verdict == verdict)
+            .count()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines = self
+            .moves
+            .iter()
+            .enumerate()
+            .map(|(i, analysis)| {
+                let mut line = format!("{:>4}. {:<5} {}", i + 1, analysis.played, analysis.verdict);
+
+                if analysis.verdict != Verdict::Best {
+                    line += &format!(
+                        " (best: {}, loss {:.0})",
+                        analysis.best, analysis.loss
+                    );
+                }
+
+                line
+            })
+            .collect::<Vec<_>>();
+
+        lines.push(format!(
+            "best: {}, good: {}, inaccuracies: {}, blunders: {}",
+            self.count(Verdict::Best),
+            self.count(Verdict::Good),
+            self.count(Verdict::Inaccuracy),
+            self.count(Verdict::Blunder)
+        ));
+
+        lines.join("\n")
+    }
+
+    pub fn to_json(&self) -> String {
+        let moves = self
+            .moves
+            .iter()
+            .map(|analysis| {
+                let values = analysis
+                    .values
+                    .iter()
+                    .map(|(direction, value)| format!("\"{}\":{}", direction, value))
+                    .collect::<Vec<_>>();
+
+                format!(
+                    "{{\"played\":\"{}\",\"best\":\"{}\",\"loss\":{},\"verdict\":\"{}\",\"values\":{{{}}}}}",
+                    analysis.played,
+                    analysis.best,
+                    analysis.loss,
+                    analysis.verdict,
+                    values.join(",")
+                )
+            })
+            .collect::<Vec<_>>();
+
+        format!("{{\"moves\":[{}]}}", moves.join(","))
+    }
+}
+
+pub fn main(args: &[String]) -> Result<(), Error> {
+    let options = Options::parse(args, OPTIONS)?;
+
+    if options.flag("help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let replay = Replay::load(
+        options
+            .string("replay")
+            .ok_or_else(|| format_err!("{}", USAGE))?,
+    )?;
+    let weights = match options.string("weights") {
+        Some(path) => Weights::load(path)?,
+        None => Weights::default(),
+    };
+    let report = analyze(&replay, &Settings::new(options.get("depth", 3)?, weights))?;
+
+    let output = match options.string("format").unwrap_or("text") {
+        "text" => report.to_text(),
+        "json" => report.to_json(),
+        format => bail!("unknown format: {}", format),
+    };
+
+    match options.string("output") {
+        Some(path) => fs::write(path, output + "\n")
+            .map_err(|e| format_err!("cannot write {}: {}", path, e))?,
+        None => println!("{}", output),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::position::Position;
+    use crate::strategy::{Random, Strategy};
+
+    fn replay(seed: u64, moves: usize) -> Replay {
+        let mut game = Game::with_seed(2048, 4, 4, seed);
+        let mut strategy = Random::new(seed);
+
+        while game.history.len() < moves {
+            match strategy.next_move(&game.board) {
+                Some(direction) => game.play(direction),
+                None => break,
+            };
+        }
+
+        game.replay()
+    }
+
+    #[test]
+    fn test_verdict_from_loss() {
+        assert_eq!(Verdict::Best, Verdict::from_loss(0.0));
+        assert_eq!(Verdict::Good, Verdict::from_loss(GOOD_LOSS));
+        assert_eq!(Verdict::Inaccuracy, Verdict::from_loss(INACCURACY_LOSS));
+        assert_eq!(Verdict::Blunder, Verdict::from_loss(2.0 * INACCURACY_LOSS));
+    }
+
+    #[test]
+    fn test_analyze() {
+        let replay = replay(4, 30);
+        let report = analyze(&replay, &Settings::new(1, Weights::default())).unwrap();
+
+        assert_eq!(30, report.moves.len());
+        assert_eq!(replay.positions().unwrap()[30], report.final_board);
+        for analysis in &report.moves {
+            assert!(analysis.loss >= 0.0);
+            assert_eq!(analysis.verdict == Verdict::Best, analysis.loss == 0.0);
+            assert!(analysis
+                .values
+                .iter()
+                .any(|&(direction, _)| direction == analysis.played));
+        }
+    }
+
+    #[test]
+    fn test_analyze_moves_which_may_lose_the_game() {
+        // up loses the game half of the time, and down always
+        let start: Position = "4,16,2,4/4,2,16,2/16,4,2,4/2,8,16,2".parse().unwrap();
+        let settings = Settings::new(2, Weights::default());
+
+        for &(played, verdict) in &[(Move::Up, Verdict::Best), (Move::Down, Verdict::Blunder)] {
+            let mut replay = replay(4, 0);
+            replay.start = Some(start.clone());
+            replay.moves = vec![played];
+
+            let report = analyze(&replay, &settings).unwrap();
+
+            assert_eq!(Move::Up, report.moves[0].best);
+            assert_eq!(verdict, report.moves[0].verdict);
+        }
+    }
+
+    #[test]
+    fn test_engine_moves_are_best() {
+        let mut game = Game::with_seed(2048, 4, 4, 1);
+        let settings = Settings::new(1, Weights::default());
+        let mut engine = Expectimax::new(1, Arc::clone(&settings.heuristic));
+
+        for _ in 0..20 {
+            let direction = engine.next_move(&game.board).unwrap();
+            game.play(direction);
+        }
+
+        let report = analyze(&game.replay(), &settings).unwrap();
+
+        assert_eq!(20, report.count(Verdict::Best));
+    }
+
+    #[test]
+    fn test_analyze_rejects_other_sizes() {
+        let mut replay = replay(4, 0);
+        replay.width = 3;
+
+        assert!(analyze(&replay, &Settings::default()).is_err());
+    }
+
+    #[test]
+    fn test_analyze_rejects_boards_the_engine_cannot_pack() {
+        let mut torus = replay(4, 2);
+        torus.topology = Topology::Torus;
+        let mut walled = replay(4, 2);
+        walled.layout = Some("..../.X../..../....".parse().unwrap());
+
+        for replay in &[torus, walled] {
+            let error = analyze(replay, &Settings::default()).err().unwrap();
+
+            assert_eq!("only games on plain grids without special tiles can be analyzed", error.to_string());
+        }
+
+        let mut big = replay(4, 0);
+        big.start = Some("65536,.,.,./.,.,.,./.,.,.,./.,.,.,.".parse().unwrap());
+        big.moves = vec![Move::Right];
+
+        let error = analyze(&big, &Settings::default()).err().unwrap();
+        assert_eq!("move 1: block 2^16 cannot be packed", error.to_string());
+    }
+
+    #[test]
+    fn test_directions() {
+        let board: Board = "2,2,4,./.,.,.,./.,.,.,./.,.,.,.".parse().unwrap();
+        let mut engine = Expectimax::new(1, Arc::clone(&Settings::default().heuristic));
+        let directions = directions(&board, &mut engine);
+
+        assert_eq!(4, directions.len());
+
+        let up = &directions[0];
+        assert_eq!(Move::Up, up.direction);
+        assert!(!up.legal);
+        assert_eq!(None, up.value);
+
+        let left = &directions[2];
+        assert_eq!(Move::Left, left.direction);
+        assert!(left.legal);
+        assert_eq!(4, left.score);
+        assert_eq!(14, left.empty);
+        assert!(left.value.is_some());
+    }
+
+    #[test]
+    fn test_directions_without_values() {
+        let mut engine = Expectimax::new(1, Arc::clone(&Settings::default().heuristic));
+        let board: Board = "2,.,./.,.,./.,.,.".parse().unwrap();
+
+        for analysis in directions(&board, &mut engine) {
+            assert_eq!(None, analysis.value);
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        let report = analyze(&replay(2, 2), &Settings::new(1, Weights::default())).unwrap();
+        let json = report.to_json();
+
+        assert!(json.starts_with("{\"moves\":[{\"played\":"));
+        assert_eq!(2, json.matches("\"verdict\"").count());
+    }
+}
@@ -0,0 +1,86 @@
+use crate::bitboard::BitBoard;
+use crate::board::{Board, Move};
+use crate::cli::Options;
+use crate::game::Game;
+use crate::strategy::{Random, Strategy};
+use failure::{bail, Error};
+use std::convert::TryFrom;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+pub const USAGE: &str = "usage: tui-2048 bench [--moves N] [--positions N] [--seed N]";
+
+const OPTIONS: &[&str] = &["moves", "positions", "seed", "help"];
+
+/// Collects positions from seeded random games, so both engines are timed on
+/// the same realistic boards.
+fn positions(count: usize, seed: u64) -> Vec<Board> {
+    let mut positions = Vec::with_capacity(count);
+    let mut game_seed = seed;
+
+    while positions.len() < count {
+        let mut game = Game::with_seed(2048, 4, 4, game_seed);
+        let mut strategy = Random::new(game_seed);
+
+        while let Some(direction) = strategy.next_move(&game.board) {
+            positions.push(game.board.clone());
+            game.play(direction);
+        }
+        game_seed += 1;
+    }
+
+    positions.truncate(count);
+    positions
+}
+
+fn time<F: FnMut(usize)>(moves: usize, mut f: F) -> Duration {
+    let start = Instant::now();
+
+    for i in 0..moves {
+        f(i);
+    }
+
+    start.elapsed()
+}
+
+pub fn main(args: &[String]) -> Result<(), Error> {
+    let options = Options::parse(args, OPTIONS)?;
+
+    if options.flag("help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let moves: usize = options.get("moves", 1_000_000)?;
+    let count: usize = options.get("positions", 10_000)?;
+    let seed: u64 = options.get("seed", 0)?;
+
+    if count == 0 {
+        bail!("--positions must be positive");
+    }
+
+    let boards = positions(count, seed);
+    let bitboards = boards
+        .iter()
+        .map(BitBoard::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let board_time = time(moves, |i| {
+        black_box(boards[i % count].try_to_move(Move::ALL[i % 4]));
+    });
+    let bitboard_time = time(moves, |i| {
+        black_box(bitboards[i % count].try_to_move(Move::ALL[i % 4]));
+    });
+
+    let rate = |elapsed: Duration| moves as f64 / elapsed.as_secs_f64();
+
+    println!("moves: {} over {} positions", moves, count);
+    println!("board:    {:>14.0} moves/sec", rate(board_time));
+    println!("bitboard: {:>14.0} moves/sec", rate(bitboard_time));
+    println!(
+        "speedup:  {:>14.1}x",
+        board_time.as_secs_f64() / bitboard_time.as_secs_f64()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,333 @@
+use crate::block;
+use crate::board::{Board, Move};
+use crate::topology::Topology;
+use crate::tile::Tile;
+use failure::{bail, Error};
+use std::convert::TryFrom;
+use std::sync::OnceLock;
+
+pub const SIZE: usize = 4;
+
+/// Largest exponent a cell can hold. Two blocks of this value do not merge
+/// here, whereas `Board` merges them into a 65536 as it merges any blocks
+/// below `block::MAX_EXPONENT`, so the two only agree on boards without two
+/// 32768 blocks to merge.
+pub const MAX_EXPONENT: u8 = 15;
+
+const ROW_MASK: u64 = 0xFFFF;
+
+/// A 4x4 board packed in 64 bits, 4 bits per block. Each nibble holds the
+/// exponent of its block, as in `Board`. Row `i` takes bits
+/// `16 * i..16 * (i + 1)` and column `j` of a row takes bits
+/// `4 * j..4 * (j + 1)`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Default)]
+pub struct BitBoard(pub u64);
+
+struct Tables {
+    left: Vec<u16>,
+    right: Vec<u16>,
+    score: Vec<u64>,
+}
+
+fn reverse_row(row: u16) -> u16 {
+    (row >> 12) | ((row >> 4) & 0x00F0) | ((row << 4) & 0x0F00) | (row << 12)
+}
+
+/// Slides one row to the left, returning the new row and the points earned.
+fn move_row_left(row: u16) -> (u16, u64) {
+    let mut cells = [0u8; SIZE];
+    let mut merged = [false; SIZE];
+    let mut score = 0;
+    let mut k = 0;
+
+    for j in 0..SIZE {
+        let cell = ((row >> (4 * j)) & 0xF) as u8;
+
+        if cell == 0 {
+            continue;
+        }
+        if k > 0 && cells[k - 1] == cell && !merged[k - 1] && cell < MAX_EXPONENT {
+            cells[k - 1] += 1;
+            merged[k - 1] = true;
+            score += block::value(cells[k - 1]);
+        } else {
+            cells[k] = cell;
+            k += 1;
+        }
+    }
+
+    let row = cells
+        .iter()
+        .enumerate()
+        .fold(0, |row, (j, &cell)| row | (cell as u16) << (4 * j));
+
+    (row, score)
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+
+    TABLES.get_or_init(|| {
+        let mut tables = Tables {
+            left: vec![0; 1 << 16],
+            right: vec![0; 1 << 16],
+            score: vec![0; 1 << 16],
+        };
+
+        for row in 0..=u16::MAX {
+            let (left, score) = move_row_left(row);
+
+            tables.left[row as usize] = left;
+            tables.score[row as usize] = score;
+            tables.right[reverse_row(row) as usize] = reverse_row(left);
+        }
+
+        tables
+    })
+}
+
+impl BitBoard {
+    pub fn get(self, row: usize, col: usize) -> u8 {
+        ((self.0 >> (16 * row + 4 * col)) & 0xF) as u8
+    }
+
+    pub fn set(self, row: usize, col: usize, exponent: u8) -> BitBoard {
+        let shift = 16 * row + 4 * col;
+
+        BitBoard((self.0 & !(0xF << shift)) | ((exponent as u64 & 0xF) << shift))
+    }
+
+    pub fn row(self, i: usize) -> u16 {
+        ((self.0 >> (16 * i)) & ROW_MASK) as u16
+    }
+
+    /// Indexes of the empty blocks, as `(row, col)`.
+    pub fn empty_indexes(self) -> Vec<(usize, usize)> {
+        (0..SIZE * SIZE)
+            .filter(|i| (self.0 >> (4 * i)) & 0xF == 0)
+            .map(|i| (i / SIZE, i % SIZE))
+            .collect()
+    }
+
+    pub fn transpose(self) -> BitBoard {
+        let x = self.0;
+        let a1 = x & 0xF0F0_0F0F_F0F0_0F0F;
+        let a2 = x & 0x0000_F0F0_0000_F0F0;
+        let a3 = x & 0x0F0F_0000_0F0F_0000;
+        let a = a1 | (a2 << 12) | (a3 >> 12);
+        let b1 = a & 0xFF00_FF00_00FF_00FF;
+        let b2 = a & 0x00FF_00FF_0000_0000;
+        let b3 = a & 0x0000_0000_FF00_FF00;
+
+        BitBoard(b1 | (b2 >> 24) | (b3 << 24))
+    }
+
+    /// Mirrors the board left to right.
+    pub fn flip_horizontal(self) -> BitBoard {
+        let x = self.0;
+
+        BitBoard(
+            ((x & 0x000F_000F_000F_000F) << 12)
+                | ((x & 0x00F0_00F0_00F0_00F0) << 4)
+                | ((x & 0x0F00_0F00_0F00_0F00) >> 4)
+                | ((x & 0xF000_F000_F000_F000) >> 12),
+        )
+    }
+
+    /// The smallest of the eight rotations and reflections of the board.
+    /// Symmetric boards share it, so it works as a key for caches.
+    pub fn canonical(self) -> BitBoard {
+        let mut board = self;
+        let mut canonical = self;
+
+        for i in 0..8 {
+            board = if i % 2 == 0 { board.transpose() } else { board.flip_horizontal() };
+            canonical = canonical.min(board);
+        }
+
+        canonical
+    }
+
+    fn move_rows(self, table: &[u16]) -> (BitBoard, u64) {
+        let tables = tables();
+        let mut board = 0;
+        let mut score = 0;
+
+        for i in 0..SIZE {
+            let row = self.row(i);
+
+            board |= (table[row as usize] as u64) << (16 * i);
+            score += tables.score[row as usize];
+        }
+
+        (BitBoard(board), score)
+    }
+
+    /// Returns the board after `direction` and the points earned by it.
+    /// Moves which a grid does not have leave the board as it is.
+    pub fn try_to_move(self, direction: Move) -> (BitBoard, u64) {
+        let tables = tables();
+
+        match direction {
+            Move::Left => self.move_rows(&tables.left),
+            Move::Right => self.move_rows(&tables.right),
+            Move::Up => {
+                let (board, score) = self.transpose().move_rows(&tables.left);
+
+                (board.transpose(), score)
+            }
+            Move::Down => {
+                let (board, score) = self.transpose().move_rows(&tables.right);
+
+                (board.transpose(), score)
+            }
+            // a grid has no diagonal moves
+            _ => (self, 0),
+        }
+    }
+}
+
+impl TryFrom<&Board> for BitBoard {
+    type Error = Error;
+
+    fn try_from(board: &Board) -> Result<BitBoard, Error> {
+        let grid = board.topology == Topology::Grid && board.mask.is_none();
+        if board.width != SIZE || board.height != SIZE || !grid {
+            bail!("only {}x{} grids without walls can be packed", SIZE, SIZE);
+        }
+
+        let mut bitboard = BitBoard::default();
+
+        for i in 0..SIZE {
+            for j in 0..SIZE {
+                let tile = board.blocks[i][j];
+                let exponent = tile.rank();
+
+                if !tile.is_plain() {
+                    bail!("special tiles cannot be packed");
+                }
+                if exponent > MAX_EXPONENT {
+                    bail!("block 2^{} cannot be packed", exponent);
+                }
+
+                bitboard = bitboard.set(i, j, exponent);
+            }
+        }
+
+        Ok(bitboard)
+    }
+}
+
+impl From<BitBoard> for Board {
+    fn from(bitboard: BitBoard) -> Board {
+        let mut board = Board::new(SIZE, SIZE);
+
+        for i in 0..SIZE {
+            for j in 0..SIZE {
+                board.blocks[i][j] = Tile::from(bitboard.get(i, j));
+            }
+        }
+
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::strategy::{Random, Strategy};
+
+    fn board(notation: &str) -> Board {
+        notation.parse().unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let blocks = board(".,2,4,8/16,32,64,128/256,512,1024,2048/4096,8192,16384,32768");
+        let bitboard = BitBoard::try_from(&blocks).unwrap();
+
+        assert_eq!(0, bitboard.get(0, 0));
+        assert_eq!(15, bitboard.get(3, 3));
+        assert_eq!(blocks, Board::from(bitboard));
+    }
+
+    #[test]
+    fn test_try_from_rejects_unpackable_boards() {
+        assert!(BitBoard::try_from(&Board::new(3, 3)).is_err());
+
+        let mut blocks = Board::new(4, 4);
+        blocks.blocks[1][2] = Tile::Block(MAX_EXPONENT + 1);
+
+        assert!(BitBoard::try_from(&blocks).is_err());
+    }
+
+    #[test]
+    fn test_transpose() {
+        let bitboard = BitBoard::default().set(0, 1, 3).set(2, 3, 5);
+        let transposed = bitboard.transpose();
+
+        assert_eq!(3, transposed.get(1, 0));
+        assert_eq!(5, transposed.get(3, 2));
+        assert_eq!(bitboard, transposed.transpose());
+    }
+
+    #[test]
+    fn test_flips() {
+        let bitboard = BitBoard::default().set(0, 1, 3).set(2, 3, 5);
+
+        let horizontal = bitboard.flip_horizontal();
+        assert_eq!(3, horizontal.get(0, 2));
+        assert_eq!(5, horizontal.get(2, 0));
+    }
+
+    #[test]
+    fn test_canonical_is_shared_by_symmetric_boards() {
+        let bitboard = BitBoard::default().set(0, 1, 3).set(2, 3, 5).set(3, 3, 1);
+        let symmetries = [
+            bitboard.transpose(),
+            bitboard.flip_horizontal(),
+            bitboard.transpose().flip_horizontal(),
+            bitboard.flip_horizontal().transpose(),
+            bitboard.transpose().flip_horizontal().transpose(),
+        ];
+
+        for symmetry in symmetries.iter() {
+            assert_eq!(bitboard.canonical(), symmetry.canonical());
+        }
+        assert_ne!(bitboard.canonical(), bitboard.set(0, 0, 1).canonical());
+    }
+
+    #[test]
+    fn test_largest_blocks_do_not_merge() {
+        let bitboard = BitBoard::default().set(0, 0, MAX_EXPONENT).set(0, 1, MAX_EXPONENT);
+
+        assert_eq!((bitboard, 0), bitboard.try_to_move(Move::Left));
+    }
+
+    #[test]
+    fn test_moves_match_board() {
+        for seed in 0..20 {
+            let mut game = Game::with_seed(2048, 4, 4, seed);
+            let mut strategy = Random::new(seed);
+
+            loop {
+                let bitboard = BitBoard::try_from(&game.board).unwrap();
+
+                for &direction in Move::ALL.iter() {
+                    let expected = game.board.try_to_move(direction);
+                    let (next, score) = bitboard.try_to_move(direction);
+
+                    assert_eq!(expected.blocks, Board::from(next).blocks);
+                    assert_eq!(expected.score, score);
+                    assert_eq!(expected.updated, next != bitboard);
+                }
+
+                match strategy.next_move(&game.board) {
+                    Some(direction) => game.play(direction),
+                    None => break,
+                };
+            }
+        }
+    }
+}
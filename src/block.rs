@@ -1,10 +1,148 @@
-pub mod block {
-    use rand::Rng;
+//! Blocks are stored as exponents: 0 is an empty block and `n` is a block
+//! with the value `2^n`.
 
-    pub fn random() -> u32 {
-        let mut rng = rand::thread_rng();
-        let value = rng.gen_range(1, 3) * 2;
+use rand::Rng;
 
-        value
+/// Largest value shown as is; bigger blocks are labelled as `2^n`.
+const MAX_PLAIN_LABEL: u64 = 1_000_000;
+
+/// Exponent of the largest block. Blocks of this exponent do not merge, so
+/// that values and scores stay within a `u64`.
+pub const MAX_EXPONENT: u8 = 62;
+
+pub fn random<R: Rng + ?Sized>(rng: &mut R) -> u8 {
+    rng.gen_range(1, 3)
+}
+
+/// How likely a new block is to be a 4 rather than a 2.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpawnRule {
+    pub four_probability: f64,
+}
+
+impl Default for SpawnRule {
+    /// The rule used by `random`.
+    fn default() -> SpawnRule {
+        SpawnRule {
+            four_probability: 0.5,
+        }
+    }
+}
+
+impl SpawnRule {
+    /// Exponents a new block can take, paired with the probability of each.
+    pub fn choices(&self) -> [(u8, f64); 2] {
+        [(1, 1.0 - self.four_probability), (2, self.four_probability)]
+    }
+
+    /// Draws the exponent of a new block.
+    pub fn pick<R: Rng + ?Sized>(&self, rng: &mut R) -> u8 {
+        if *self == SpawnRule::default() {
+            random(rng)
+        } else if rng.gen_bool(self.four_probability) {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+/// Value of a block with the given exponent.
+pub fn value(exponent: u8) -> u64 {
+    if exponent == 0 {
+        0
+    } else {
+        1 << exponent
+    }
+}
+
+/// Exponent of the largest block whose value does not exceed `value`.
+pub fn exponent(value: u64) -> u8 {
+    if value == 0 {
+        0
+    } else {
+        63 - value.leading_zeros() as u8
+    }
+}
+
+/// Text shown for a block, empty for an empty block.
+pub fn label(exponent: u8) -> String {
+    match value(exponent) {
+        0 => String::new(),
+        value if value < MAX_PLAIN_LABEL => value.to_string(),
+        _ => format!("2^{}", exponent),
+    }
+}
+
+/// Exponent of a block written as its value or as `2^n`, the inverse of
+/// `label`. `None` unless it is a power of two from 2 to `2^MAX_EXPONENT`.
+pub fn parse(text: &str) -> Option<u8> {
+    let exponent = match text.strip_prefix("2^") {
+        Some(exponent) => exponent.parse().ok()?,
+        None => {
+            let value = text.parse::<u64>().ok()?;
+            if !value.is_power_of_two() {
+                return None;
+            }
+            exponent(value)
+        }
+    };
+
+    Some(exponent).filter(|&exponent| (1..=MAX_EXPONENT).contains(&exponent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_value_and_exponent() {
+        assert_eq!(0, value(0));
+        assert_eq!(2048, value(11));
+        assert_eq!(1 << 40, value(40));
+        assert_eq!(0, exponent(0));
+        assert_eq!(11, exponent(2048));
+        assert_eq!(11, exponent(3000));
+        assert_eq!(40, exponent(1 << 40));
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!("", label(0));
+        assert_eq!("2048", label(11));
+        assert_eq!("2^40", label(40));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Some(1), parse("2"));
+        assert_eq!(Some(11), parse("2048"));
+        assert_eq!(Some(40), parse("2^40"));
+        assert_eq!(Some(40), parse(&label(40)));
+        assert_eq!(None, parse("1"));
+        assert_eq!(None, parse("0"));
+        assert_eq!(None, parse("6"));
+        assert_eq!(Some(62), parse("2^62"));
+        assert_eq!(None, parse("2^63"));
+        assert_eq!(None, parse("9223372036854775808"));
+        assert_eq!(None, parse("2^64"));
+        assert_eq!(None, parse("x"));
+    }
+
+    #[test]
+    fn test_spawn_rule_pick() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let twos = SpawnRule {
+            four_probability: 0.0,
+        };
+        let fours = SpawnRule {
+            four_probability: 1.0,
+        };
+
+        for _ in 0..100 {
+            assert_eq!(1, twos.pick(&mut rng));
+            assert_eq!(2, fours.pick(&mut rng));
+        }
     }
 }
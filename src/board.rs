@@ -1,72 +1,203 @@
+use crate::layout::{Cell, Layout};
+use crate::merge::{Classic, MergeRule, Slide};
+use crate::tile::Tile;
+use crate::topology::Topology;
+use failure::{bail, Error};
 use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Move {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// The diagonals of hexagonal boards, see `Topology::Hex`.
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+    /// Across the layers of a cube, see `Topology::Cube`.
+    In,
+    Out,
+}
+
+impl Move {
+    /// The moves of a grid.
+    pub const ALL: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+    /// The moves of a hexagonal board.
+    pub const HEX: [Move; 6] = [
+        Move::Up,
+        Move::Down,
+        Move::UpLeft,
+        Move::UpRight,
+        Move::DownLeft,
+        Move::DownRight,
+    ];
+
+    /// The moves of a cube.
+    pub const CUBE: [Move; 6] = [
+        Move::Up,
+        Move::Down,
+        Move::Left,
+        Move::Right,
+        Move::In,
+        Move::Out,
+    ];
+
+    /// Single letter used to write moves down, as in replays.
+    pub fn letter(self) -> char {
+        match self {
+            Move::Up => 'U',
+            Move::Down => 'D',
+            Move::Left => 'L',
+            Move::Right => 'R',
+            Move::UpLeft => 'Q',
+            Move::UpRight => 'E',
+            Move::DownLeft => 'A',
+            Move::DownRight => 'C',
+            Move::In => 'I',
+            Move::Out => 'O',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Move> {
+        Move::ALL
+            .iter()
+            .chain(Move::HEX.iter())
+            .chain(Move::CUBE.iter())
+            .copied()
+            .find(|direction| direction.letter() == letter)
+    }
+
+    pub fn opposite(self) -> Move {
+        match self {
+            Move::Up => Move::Down,
+            Move::Down => Move::Up,
+            Move::Left => Move::Right,
+            Move::Right => Move::Left,
+            Move::UpLeft => Move::DownRight,
+            Move::UpRight => Move::DownLeft,
+            Move::DownLeft => Move::UpRight,
+            Move::DownRight => Move::UpLeft,
+            Move::In => Move::Out,
+            Move::Out => Move::In,
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Move::Up => "up",
+            Move::Down => "down",
+            Move::Left => "left",
+            Move::Right => "right",
+            Move::UpLeft => "up-left",
+            Move::UpRight => "up-right",
+            Move::DownLeft => "down-left",
+            Move::DownRight => "down-right",
+            Move::In => "in",
+            Move::Out => "out",
+        };
+
+        write!(f, "{}", name)
+    }
+}
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Board {
     pub width: usize,
     pub height: usize,
-    pub blocks: Vec<Vec<u32>>,
-    pub updated: bool
+    /// Tiles of the places: mostly blocks, stored by exponent (see `block`),
+    /// and the special tiles of `Tile`.
+    pub blocks: Vec<Vec<Tile>>,
+    pub updated: bool,
+    /// Points earned by the merges of the move which produced this board.
+    pub score: u64,
+    /// Which places of the rectangle belong to the board and how they are
+    /// connected.
+    pub topology: Topology,
+    /// Walls and holes of the board, if it has any, see `Layout`. They turn
+    /// and mirror with the board.
+    pub mask: Option<Arc<Layout>>,
 }
 
 impl Board {
     pub fn new(width: usize, height: usize) -> Board {
+        Board::with_topology(width, height, Topology::Grid)
+    }
+
+    pub fn with_topology(width: usize, height: usize, topology: Topology) -> Board {
         Board {
-            width: width,
-            height: height,
-            blocks: vec![vec![0; width]; height],
-            updated: false
+            width,
+            height,
+            blocks: vec![vec![Tile::Empty; width]; height],
+            updated: false,
+            score: 0,
+            topology,
+            mask: None,
         }
     }
 
-    pub fn try_to_move_up(&self) -> Board {
-        let mut board = Board::new(self.width, self.height);
-        let mut merged = vec![vec![false; self.width]; self.height];
-
-        for j in 0..self.width {
-            let mut k = 0;
-
-            for i in 0..self.height {
-                if self.blocks[i][j] > 0 {
-                    if k == 0 || board.blocks[k - 1][j] != self.blocks[i][j] || merged[k - 1][j] {
-                        board.blocks[k][j] = self.blocks[i][j];
-                        if k != i {
-                            board.updated = true;
-                        }
-                        k += 1;
-                    } else {
-                        board.blocks[k - 1][j] = board.blocks[k - 1][j] * 2;
-                        merged[k - 1][j] = true;
-                        board.updated = true;
-                    }
-                }
+    pub fn try_to_move(&self, direction: Move) -> Board {
+        match direction {
+            Move::Up => self.try_to_move_up(),
+            Move::Down => self.try_to_move_down(),
+            Move::Left => self.try_to_move_left(),
+            Move::Right => self.try_to_move_right(),
+            direction => self.try_to_move_with(direction, &Classic),
+        }
+    }
+
+    /// Slides the blocks towards `direction` as `rule` says, merging the
+    /// blocks it allows. A block merges at most once per move. Rocks stay
+    /// where they are and bombs which merge clear the places around them.
+    pub fn try_to_move_with(&self, direction: Move, rule: &dyn MergeRule) -> Board {
+        // every place of a line is written below, so only rocks are kept
+        let mut board = Board {
+            updated: false,
+            score: 0,
+            ..self.clone()
+        };
+        let mut explosions = Vec::new();
+
+        for line in self.lines(direction) {
+            let tiles = line
+                .iter()
+                .map(|&(i, j)| self.blocks[i][j])
+                .collect::<Vec<_>>();
+            let (slid, score, exploded) = if tiles.iter().all(|tile| tile.is_plain()) {
+                let blocks = tiles.iter().map(|tile| tile.rank()).collect::<Vec<_>>();
+                let (slid, score) = match rule.slide() {
+                    Slide::Full if self.topology.wraps() => slide_wrapping(&blocks, rule),
+                    Slide::Full => slide_fully(&blocks, rule),
+                    Slide::OneStep => slide_one_step(&blocks, rule),
+                };
+
+                (slid.into_iter().map(Tile::from).collect(), score, Vec::new())
+            } else {
+                slide_tiles(&tiles, rule)
+            };
+
+            for (&(i, j), &tile) in line.iter().zip(&slid) {
+                board.blocks[i][j] = tile;
             }
+            board.score += score;
+            board.updated |= slid != tiles;
+            explosions.extend(exploded.into_iter().map(|k| line[k]));
         }
 
-        board
-    }
+        for place in explosions {
+            for &direction in self.topology.directions() {
+                let neighbour = self.topology.neighbour(self.width, self.height, place, direction);
 
-    pub fn try_to_move_down(&self) -> Board {
-        let mut board = Board::new(self.width, self.height);
-        let mut merged = vec![vec![false; self.width]; self.height];
-
-        for j in 0..self.width {
-            let mut k = self.height - 1;
-
-            for i in (0..self.height).rev() {
-                if self.blocks[i][j] > 0 {
-                    if k == self.height - 1 || board.blocks[k + 1][j] != self.blocks[i][j] || merged[k + 1][j] {
-                        board.blocks[k][j] = self.blocks[i][j];
-                        if k != i {
-                            board.updated = true;
-                        }
-                        if k > 0 {
-                            k -= 1;
-                        }
-                    } else {
-                        board.blocks[k + 1][j] = board.blocks[k + 1][j] * 2;
-                        merged[k + 1][j] = true;
-                        board.updated = true;
-                    }
+                if let Some((i, j)) = neighbour.filter(|&(i, j)| self.contains(i, j)) {
+                    board.blocks[i][j] = Tile::Empty;
                 }
             }
         }
@@ -74,106 +205,440 @@ impl Board {
         board
     }
 
+    /// The lines blocks slide along towards `direction`, each starting at
+    /// the edge they slide to. Walls, holes and rocks split the lines of the
+    /// topology into shorter ones.
+    pub fn lines(&self, direction: Move) -> Vec<Vec<(usize, usize)>> {
+        let lines = self.topology.lines(self.width, self.height, direction);
+        let rock = |i: usize, j: usize| matches!(self.blocks[i][j], Tile::Rock(_));
+
+        if self.mask.is_none() && !self.blocks.iter().flatten().any(|tile| matches!(tile, Tile::Rock(_))) {
+            return lines;
+        }
+
+        lines
+            .iter()
+            .flat_map(|line| line.split(|&(i, j)| !self.contains(i, j) || rock(i, j)))
+            .filter(|line| !line.is_empty())
+            .map(<[_]>::to_vec)
+            .collect()
+    }
+
+    /// Empty places on the edge opposite to `direction` in the lines which
+    /// changed since `before`, where new blocks enter when blocks move one
+    /// step at a time.
+    pub fn entry_indexes(&self, before: &Board, direction: Move) -> Vec<(usize, usize)> {
+        self.lines(direction)
+            .iter()
+            .filter(|line| line.iter().any(|&(i, j)| self.blocks[i][j] != before.blocks[i][j]))
+            .map(|line| *line.last().unwrap())
+            .filter(|&(i, j)| self.blocks[i][j].is_empty())
+            .collect()
+    }
+
+    pub fn try_to_move_up(&self) -> Board {
+        self.try_to_move_with(Move::Up, &Classic)
+    }
+
+    pub fn try_to_move_down(&self) -> Board {
+        self.try_to_move_with(Move::Down, &Classic)
+    }
+
     pub fn try_to_move_left(&self) -> Board {
-        let mut board = Board::new(self.width, self.height);
-        let mut merged = vec![vec![false; self.width]; self.height];
+        self.try_to_move_with(Move::Left, &Classic)
+    }
 
-        for i in 0..self.height {
-            let mut k = 0;
+    pub fn try_to_move_right(&self) -> Board {
+        self.try_to_move_with(Move::Right, &Classic)
+    }
 
+    pub fn empty_indexes(&self) -> Vec<(usize, usize)> {
+        let mut indexes = Vec::new();
+
+        for i in 0..self.height {
             for j in 0..self.width {
-                if self.blocks[i][j] > 0 {
-                    if k == 0 || board.blocks[i][k - 1] != self.blocks[i][j] || merged[i][k - 1] {
-                        board.blocks[i][k] = self.blocks[i][j];
-                        if k != j {
-                            board.updated = true;
-                        }
-                        k += 1;
-                    } else {
-                        board.blocks[i][k - 1] = board.blocks[i][k - 1] * 2;
-                        merged[i][k - 1] = true;
-                        board.updated = true;
-                    }
+                if self.blocks[i][j].is_empty() && self.contains(i, j) {
+                    indexes.push((i, j));
                 }
             }
         }
 
-        board
+        indexes
     }
 
-    pub fn try_to_move_right(&self) -> Board {
-        let mut board = Board::new(self.width, self.height);
-        let mut merged = vec![vec![false; self.width]; self.height];
+    pub fn pick_empty_index<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<(usize, usize)> {
+        self.empty_indexes().choose(rng).copied()
+    }
+
+    /// Chance of each block being picked by `pick_empty_index`: the empty
+    /// blocks share it evenly.
+    pub fn spawn_probabilities(&self) -> Vec<Vec<f64>> {
+        let indexes = self.empty_indexes();
+        let mut probabilities = vec![vec![0.0; self.width]; self.height];
+
+        for &(row, col) in &indexes {
+            probabilities[row][col] = 1.0 / indexes.len() as f64;
+        }
+
+        probabilities
+    }
+
+    pub fn has_empty_block(&self) -> bool {
+        !self.empty_indexes().is_empty()
+    }
+
+    /// Whether the place at `row`, `col` belongs to the board and is not a
+    /// wall or a hole, see `Topology` and `Layout`.
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        self.topology.contains(self.width, self.height, row, col) && self.cell(row, col) == Cell::Open
+    }
+
+    /// What the place at `row`, `col` is, open when the board has no mask.
+    pub fn cell(&self, row: usize, col: usize) -> Cell {
+        self.mask
+            .as_ref()
+            .map_or(Cell::Open, |mask| mask.cells[row][col])
+    }
+
+    /// Whether any move would change the board.
+    pub fn can_move(&self) -> bool {
+        self.can_move_with(&Classic)
+    }
+
+    /// Whether any move would change the board when blocks merge by `rule`.
+    pub fn can_move_with(&self, rule: &dyn MergeRule) -> bool {
+        if self.has_empty_block() {
+            return true;
+        }
 
         for i in 0..self.height {
-            let mut k = self.width - 1;
-
-            for j in (0..self.width).rev() {
-                if self.blocks[i][j] > 0 {
-                    if k == self.width - 1 || board.blocks[i][k + 1] != self.blocks[i][j] || merged[i][k + 1] {
-                        board.blocks[i][k] = self.blocks[i][j];
-                        if k != j {
-                            board.updated = true;
-                        }
-                        if k > 0 {
-                            k -= 1;
+            for j in 0..self.width {
+                let block = self.blocks[i][j];
+                if !self.contains(i, j) {
+                    continue;
+                }
+
+                for &direction in self.topology.directions() {
+                    let neighbour =
+                        self.topology
+                            .neighbour(self.width, self.height, (i, j), direction);
+
+                    if let Some((row, col)) = neighbour.filter(|&(row, col)| self.contains(row, col)) {
+                        if block.merge(self.blocks[row][col], rule).is_some() {
+                            return true;
                         }
-                    } else {
-                        board.blocks[i][k + 1] = board.blocks[i][k + 1] * 2;
-                        merged[i][k + 1] = true;
-                        board.updated = true;
                     }
                 }
             }
         }
 
+        false
+    }
+
+    pub fn put_new_block(self, row: usize, col: usize, exponent: u8) -> Board {
+        let mut board = self;
+
+        board.blocks[row][col] = Tile::from(exponent);
+
         board
     }
 
-    pub fn pick_empty_index(&self) -> Option<(usize, usize)> {
-        let mut indexes = Vec::new();
+    /// Whether a block or a bomb has the given exponent.
+    pub fn has_block_with(&self, exponent: u8) -> bool {
+        self.blocks
+            .iter()
+            .flatten()
+            .any(|tile| tile.rank() == exponent)
+    }
+
+    /// Counts a move down on every rock and removes those whose time is up.
+    pub fn erode_rocks(&mut self) {
+        for tile in self.blocks.iter_mut().flatten() {
+            if let Tile::Rock(moves) = tile {
+                *tile = match *moves {
+                    0 | 1 => Tile::Empty,
+                    moves => Tile::Rock(moves - 1),
+                };
+            }
+        }
+    }
+
+    /// Exponents of the blocks and bombs, 0 for any other tile.
+    pub fn ranks(&self) -> Vec<Vec<u8>> {
+        self.blocks
+            .iter()
+            .map(|row| row.iter().map(|tile| tile.rank()).collect())
+            .collect()
+    }
+
+    /// A `width` x `height` board with the tile and the cell of every place
+    /// `(i, j)` moved to `place(i, j)`. The topology is kept as is, so only
+    /// grids and tori can be turned or mirrored.
+    fn rearranged<F>(&self, width: usize, height: usize, place: F) -> Board
+    where
+        F: Fn(usize, usize) -> (usize, usize),
+    {
+        let mut board = Board::with_topology(width, height, self.topology);
+        let mut cells = vec![vec![Cell::Open; width]; height];
 
         for i in 0..self.height {
             for j in 0..self.width {
-                if self.blocks[i][j] == 0 {
-                    indexes.push((i, j));
-                }
+                let (row, col) = place(i, j);
+
+                board.blocks[row][col] = self.blocks[i][j];
+                cells[row][col] = self.cell(i, j);
             }
         }
+        board.mask = self.mask.as_ref().map(|_| Arc::new(Layout { cells }));
 
-        let mut rng = rand::thread_rng();
+        board
+    }
 
-        indexes.choose(&mut rng).map(|index| (index.0, index.1))
+    pub fn transpose(&self) -> Board {
+        self.rearranged(self.height, self.width, |i, j| (j, i))
     }
 
-    pub fn has_empty_block(&self) -> bool {
-        self.pick_empty_index().is_some()
+    /// Mirrors the board left to right.
+    pub fn flip_horizontal(&self) -> Board {
+        let width = self.width;
+
+        self.rearranged(width, self.height, |i, j| (i, width - 1 - j))
     }
 
-    pub fn put_new_block(self, row: usize, col: usize, value: u32) -> Board {
-        let mut board = self.clone();
+    /// Mirrors the board top to bottom.
+    pub fn flip_vertical(&self) -> Board {
+        let height = self.height;
+
+        self.rearranged(self.width, height, |i, j| (height - 1 - i, j))
+    }
 
-        board.blocks[row][col] = value;
+    pub fn rotate_cw(&self) -> Board {
+        self.transpose().flip_horizontal()
+    }
 
-        board
+    pub fn rotate_ccw(&self) -> Board {
+        self.transpose().flip_vertical()
     }
 
-    pub fn has_block_with(&self, value: u32) -> bool {
-        for row in &self.blocks {
-            for block in row {
-                if *block == value {
-                    return true;
+    /// Rotations and reflections of the board which keep its shape: all
+    /// eight for a square board, and otherwise only the rotations which
+    /// keep it, the identity and the half turn.
+    pub fn symmetries(&self) -> Vec<Board> {
+        let mut symmetries = vec![
+            Board {
+                updated: false,
+                score: 0,
+                ..self.clone()
+            },
+            self.flip_horizontal().flip_vertical(),
+        ];
+
+        if self.width == self.height {
+            symmetries.push(self.flip_horizontal());
+            symmetries.push(self.flip_vertical());
+            symmetries.push(self.transpose());
+            symmetries.push(self.rotate_cw());
+            symmetries.push(self.rotate_ccw());
+            symmetries.push(self.rotate_cw().flip_vertical());
+        }
+
+        symmetries
+    }
+
+    /// The symmetry with the smallest blocks in row-major order. Boards
+    /// which are rotations or reflections of each other share it.
+    pub fn canonical(&self) -> Board {
+        self.symmetries()
+            .into_iter()
+            .min_by(|a, b| a.blocks.cmp(&b.blocks))
+            .unwrap()
+    }
+
+    pub fn max_exponent(&self) -> u8 {
+        self.blocks.iter().flatten().map(|tile| tile.rank()).max().unwrap_or(0)
+    }
+}
+
+/// Slides the blocks of a line, first block first, as far as they go and
+/// returns the line and the points its merges earned.
+fn slide_fully(blocks: &[u8], rule: &dyn MergeRule) -> (Vec<u8>, u64) {
+    let mut line = vec![0; blocks.len()];
+    let mut merged = vec![false; blocks.len()];
+    let mut score = 0;
+    let mut k = 0;
+
+    for &block in blocks.iter().filter(|&&block| block > 0) {
+        if k > 0 && !merged[k - 1] {
+            if let Some(result) = rule.merge(line[k - 1], block) {
+                line[k - 1] = result;
+                score += rule.value(result);
+                merged[k - 1] = true;
+                continue;
+            }
+        }
+
+        line[k] = block;
+        k += 1;
+    }
+
+    (line, score)
+}
+
+/// Slides a line holding special tiles as far as they go, as `slide_fully`
+/// does with blocks. Tiles which cancel out free their place for the next
+/// ones. Also returns where bombs went off.
+fn slide_tiles(tiles: &[Tile], rule: &dyn MergeRule) -> (Vec<Tile>, u64, Vec<usize>) {
+    let mut line = vec![Tile::Empty; tiles.len()];
+    let mut merged = vec![false; tiles.len()];
+    let mut exploded = Vec::new();
+    let mut score = 0;
+    let mut k = 0;
+
+    for &tile in tiles.iter().filter(|tile| !tile.is_empty()) {
+        if k > 0 && !merged[k - 1] {
+            if let Some((result, bomb)) = line[k - 1].merge(tile, rule) {
+                score += rule.value(result.rank());
+                if bomb {
+                    exploded.push(k - 1);
+                }
+
+                if result.is_empty() {
+                    k -= 1;
+                    line[k] = Tile::Empty;
+                } else {
+                    line[k - 1] = result;
+                    merged[k - 1] = true;
                 }
+                continue;
             }
         }
 
-        return false;
+        line[k] = tile;
+        k += 1;
     }
+
+    (line, score, exploded)
+}
+
+/// Moves the blocks of a line which wraps around: the block on the edge passes
+/// through it and merges with the block at the other end of the line if they
+/// merge, then the other blocks slide fully and the merged block follows
+/// them.
+fn slide_wrapping(blocks: &[u8], rule: &dyn MergeRule) -> (Vec<u8>, u64) {
+    let placed = blocks.iter().copied().filter(|&block| block > 0).collect::<Vec<_>>();
+
+    let merged = match placed.as_slice() {
+        [first, .., last] => rule.merge(*last, *first),
+        _ => None,
+    };
+    let result = match merged {
+        Some(result) => result,
+        None => return slide_fully(blocks, rule),
+    };
+
+    let (mut line, score) = slide_fully(&placed[1..placed.len() - 1], rule);
+    let k = line.iter().take_while(|&&block| block > 0).count();
+    line.resize(blocks.len(), 0);
+    line[k] = result;
+
+    (line, score + rule.value(result))
 }
 
+/// Moves the blocks of a line one step: the first block which can move into
+/// an empty place or merge does, and every block behind it follows.
+fn slide_one_step(blocks: &[u8], rule: &dyn MergeRule) -> (Vec<u8>, u64) {
+    let mut line = blocks.to_vec();
+    let mut score = 0;
+
+    for k in 1..line.len() {
+        if line[k] == 0 {
+            continue;
+        }
+
+        let result = match line[k - 1] {
+            0 => Some(line[k]),
+            block => rule.merge(block, line[k]),
+        };
+
+        if let Some(result) = result {
+            if line[k - 1] > 0 {
+                score += rule.value(result);
+            }
+            line[k - 1] = result;
+            line.copy_within(k + 1.., k);
+            *line.last_mut().unwrap() = 0;
+            break;
+        }
+    }
+
+    (line, score)
+}
+
+/// Boards are written row by row, top first, with `/` between rows and `,`
+/// between blocks. A block is `.` when empty and its value otherwise, as in
+/// `2,.,.,./.,4,.,./.,.,.,./.,.,.,2048`.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows = self
+            .blocks
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(Tile::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", rows.join("/"))
+    }
+}
+
+/// Reads the notation written by `Display`. Blocks may also be given as
+/// `2^n`.
+impl FromStr for Board {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Board, Error> {
+        let blocks = text
+            .trim()
+            .split('/')
+            .map(|row| {
+                row.split(',')
+                    .map(|cell| cell.trim().parse())
+                    .collect::<Result<Vec<Tile>, Error>>()
+            })
+            .collect::<Result<Vec<Vec<Tile>>, Error>>()?;
+
+        let width = blocks[0].len();
+        if blocks.iter().any(|row| row.len() != width) {
+            bail!("rows of `{}` have different lengths", text);
+        }
+
+        Ok(Board {
+            width,
+            height: blocks.len(),
+            blocks,
+            updated: false,
+            score: 0,
+            topology: Topology::Grid,
+            mask: None,
+        })
+    }
+}
+
+#[allow(clippy::unnecessary_cast)]
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::merge::{Fibonacci, Threes};
+
+    fn tiles(ranks: Vec<Vec<u8>>) -> Vec<Vec<Tile>> {
+        ranks
+            .into_iter()
+            .map(|row| row.into_iter().map(Tile::from).collect())
+            .collect()
+    }
 
     #[test]
     fn test_new() {
@@ -188,494 +653,425 @@ mod tests {
 
         for row in board.blocks {
             for block in row {
-                assert_eq!(block, 0);
+                assert_eq!(block, Tile::Empty);
             }
         }
     }
 
     #[test]
     fn test_try_to_move_up_merge() {
-        let blocks = vec![
-            vec![0, 4, 4, 0],
-            vec![0, 2, 4, 0],
-            vec![0, 0, 2, 0],
-            vec![0, 0, 0, 0],
-        ];
-        let expected_blocks = vec![
-            vec![0, 4, 8, 0],
-            vec![0, 2, 2, 0],
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = ".,4,4,./.,2,4,./.,.,2,./.,.,.,.".parse().unwrap();
         let next_board = board.try_to_move_up();
 
         assert!(next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,4,8,./.,2,2,./.,.,.,./.,.,.,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_up_merge_only_once() {
-        let blocks = vec![
-            vec![0, 4, 4, 0],
-            vec![0, 2, 4, 0],
-            vec![0, 0, 4, 0],
-            vec![0, 0, 4, 0],
-        ];
-        let expected_blocks = vec![
-            vec![0, 4, 8, 0],
-            vec![0, 2, 8, 0],
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = ".,4,4,./.,2,4,./.,.,4,./.,.,4,.".parse().unwrap();
         let next_board = board.try_to_move_up();
 
         assert!(next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,4,8,./.,2,8,./.,.,.,./.,.,.,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_up_move() {
-        let blocks = vec![
-            vec![0, 4, 0, 0],
-            vec![0, 2, 4, 0],
-            vec![0, 0, 2, 0],
-            vec![0, 0, 0, 0],
-        ];
-        let expected_blocks = vec![
-            vec![0, 4, 4, 0],
-            vec![0, 2, 2, 0],
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = ".,4,.,./.,2,4,./.,.,2,./.,.,.,.".parse().unwrap();
         let next_board = board.try_to_move_up();
 
         assert!(next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,4,4,./.,2,2,./.,.,.,./.,.,.,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_up_not_updated() {
-        let blocks = vec![
-            vec![0, 4, 4, 0],
-            vec![0, 2, 2, 0],
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-        let expected_blocks = vec![
-            vec![0, 4, 4, 0],
-            vec![0, 2, 2, 0],
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = ".,4,4,./.,2,2,./.,.,.,./.,.,.,.".parse().unwrap();
         let next_board = board.try_to_move_up();
 
         assert!(!next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,4,4,./.,2,2,./.,.,.,./.,.,.,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_down_merge() {
-        let blocks = vec![
-            vec![0, 4, 4, 0],
-            vec![2, 2, 4, 0],
-            vec![0, 4, 2, 0],
-            vec![0, 0, 0, 0],
-        ];
-        let expected_blocks = vec![
-            vec![0, 0, 0, 0],
-            vec![0, 4, 0, 0],
-            vec![0, 2, 8, 0],
-            vec![2, 4, 2, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = ".,4,4,./2,2,4,./.,4,2,./.,.,.,.".parse().unwrap();
         let next_board = board.try_to_move_down();
 
         assert!(next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,.,.,./.,4,.,./.,2,8,./2,4,2,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_down_merge_only_once() {
-        let blocks = vec![
-            vec![0, 4, 4, 0],
-            vec![2, 2, 4, 0],
-            vec![0, 4, 4, 0],
-            vec![0, 0, 4, 0],
-        ];
-        let expected_blocks = vec![
-            vec![0, 0, 0, 0],
-            vec![0, 4, 0, 0],
-            vec![0, 2, 8, 0],
-            vec![2, 4, 8, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = ".,4,4,./2,2,4,./.,4,4,./.,.,4,.".parse().unwrap();
         let next_board = board.try_to_move_down();
 
         assert!(next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,.,.,./.,4,.,./.,2,8,./2,4,8,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_down_move() {
-        let blocks = vec![
-            vec![0, 0, 0, 0],
-            vec![0, 4, 4, 0],
-            vec![0, 0, 2, 0],
-            vec![0, 0, 4, 2],
-        ];
-        let expected_blocks = vec![
-            vec![0, 0, 0, 0],
-            vec![0, 0, 4, 0],
-            vec![0, 0, 2, 0],
-            vec![0, 4, 4, 2],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = ".,.,.,./.,4,4,./.,.,2,./.,.,4,2".parse().unwrap();
         let next_board = board.try_to_move_down();
 
         assert!(next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,.,.,./.,.,4,./.,.,2,./.,4,4,2", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_down_not_updated() {
-        let blocks = vec![
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 0],
-            vec![0, 4, 4, 0],
-            vec![0, 2, 2, 0],
-        ];
-        let expected_blocks = vec![
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 0],
-            vec![0, 4, 4, 0],
-            vec![0, 2, 2, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = ".,.,.,./.,.,.,./.,4,4,./.,2,2,.".parse().unwrap();
         let next_board = board.try_to_move_down();
 
         assert!(!next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,.,.,./.,.,.,./.,4,4,./.,2,2,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_down_when_column_is_full() {
-        let blocks = vec![
-            vec![4, 0, 0, 0],
-            vec![2, 0, 0, 0],
-            vec![8, 0, 0, 0],
-            vec![16, 0, 0, 0],
-        ];
-        let expected_blocks = vec![
-            vec![4, 0, 0, 0],
-            vec![2, 0, 0, 0],
-            vec![8, 0, 0, 0],
-            vec![16, 0, 0, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = "4,.,.,./2,.,.,./8,.,.,./16,.,.,.".parse().unwrap();
         let next_board = board.try_to_move_down();
 
         assert!(!next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!("4,.,.,./2,.,.,./8,.,.,./16,.,.,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_left_merge() {
-        let blocks = vec![
-            vec![4, 4, 0, 0],
-            vec![4, 0, 0, 0],
-            vec![4, 2, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-        let expected_blocks = vec![
-            vec![8, 0, 0, 0],
-            vec![4, 0, 0, 0],
-            vec![4, 2, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = "4,4,.,./4,.,.,./4,2,.,./.,.,.,.".parse().unwrap();
         let next_board = board.try_to_move_left();
 
         assert!(next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!("8,.,.,./4,.,.,./4,2,.,./.,.,.,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_left_merge_only_once() {
-        let blocks = vec![
-            vec![0, 4, 4, 0],
-            vec![2, 2, 4, 0],
-            vec![0, 4, 2, 0],
-            vec![0, 0, 0, 0],
-        ];
-        let expected_blocks = vec![
-            vec![8, 0, 0, 0],
-            vec![4, 4, 0, 0],
-            vec![4, 2, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = ".,4,4,./2,2,4,./.,4,2,./.,.,.,.".parse().unwrap();
         let next_board = board.try_to_move_left();
 
         assert!(next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!("8,.,.,./4,4,.,./4,2,.,./.,.,.,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_left_move() {
-        let blocks = vec![
-            vec![2, 0, 0, 0],
-            vec![0, 4, 0, 0],
-            vec![0, 0, 2, 0],
-            vec![0, 0, 4, 2],
-        ];
-        let expected_blocks = vec![
-            vec![2, 0, 0, 0],
-            vec![4, 0, 0, 0],
-            vec![2, 0, 0, 0],
-            vec![4, 2, 0, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = "2,.,.,./.,4,.,./.,.,2,./.,.,4,2".parse().unwrap();
         let next_board = board.try_to_move_left();
 
         assert!(next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!("2,.,.,./4,.,.,./2,.,.,./4,2,.,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_left_not_updated() {
-        let blocks = vec![
-            vec![0, 0, 0, 0],
-            vec![2, 0, 0, 0],
-            vec![4, 2, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-        let expected_blocks = vec![
-            vec![0, 0, 0, 0],
-            vec![2, 0, 0, 0],
-            vec![4, 2, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = ".,.,.,./2,.,.,./4,2,.,./.,.,.,.".parse().unwrap();
         let next_board = board.try_to_move_left();
 
         assert!(!next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,.,.,./2,.,.,./4,2,.,./.,.,.,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_right_merge() {
-        let blocks = vec![
-            vec![4, 4, 0, 0],
-            vec![4, 0, 0, 0],
-            vec![4, 2, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-        let expected_blocks = vec![
-            vec![0, 0, 0, 8],
-            vec![0, 0, 0, 4],
-            vec![0, 0, 4, 2],
-            vec![0, 0, 0, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = "4,4,.,./4,.,.,./4,2,.,./.,.,.,.".parse().unwrap();
         let next_board = board.try_to_move_right();
 
         assert!(next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,.,.,8/.,.,.,4/.,.,4,2/.,.,.,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_right_merge_only_once() {
-        let blocks = vec![
-            vec![0, 4, 4, 0],
-            vec![2, 2, 4, 0],
-            vec![0, 4, 2, 0],
-            vec![0, 0, 0, 0],
-        ];
-        let expected_blocks = vec![
-            vec![0, 0, 0, 8],
-            vec![0, 0, 4, 4],
-            vec![0, 0, 4, 2],
-            vec![0, 0, 0, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = ".,4,4,./2,2,4,./.,4,2,./.,.,.,.".parse().unwrap();
         let next_board = board.try_to_move_right();
 
         assert!(next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,.,.,8/.,.,4,4/.,.,4,2/.,.,.,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_right_move() {
-        let blocks = vec![
-            vec![2, 0, 0, 0],
-            vec![0, 4, 0, 0],
-            vec![0, 0, 2, 0],
-            vec![0, 0, 4, 2],
-        ];
-        let expected_blocks = vec![
-            vec![0, 0, 0, 2],
-            vec![0, 0, 0, 4],
-            vec![0, 0, 0, 2],
-            vec![0, 0, 4, 2],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = "2,.,.,./.,4,.,./.,.,2,./.,.,4,2".parse().unwrap();
         let next_board = board.try_to_move_right();
 
         assert!(next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,.,.,2/.,.,.,4/.,.,.,2/.,.,4,2", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_right_not_updated() {
-        let blocks = vec![
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 2],
-            vec![0, 0, 4, 2],
-            vec![0, 0, 0, 0],
-        ];
-        let expected_blocks = vec![
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 2],
-            vec![0, 0, 4, 2],
-            vec![0, 0, 0, 0],
-        ];
-
-        let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
-        };
+        let board: Board = ".,.,.,./.,.,.,2/.,.,4,2/.,.,.,.".parse().unwrap();
         let next_board = board.try_to_move_right();
 
         assert!(!next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+        assert_eq!(".,.,.,./.,.,.,2/.,.,4,2/.,.,.,.", next_board.to_string());
     }
 
     #[test]
     fn test_try_to_move_right_when_row_is_full() {
-        let blocks = vec![
-            vec![4, 2, 8, 16],
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-        let expected_blocks = vec![
-            vec![4, 2, 8, 16],
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
+        let board: Board = "4,2,8,16/.,.,.,./.,.,.,./.,.,.,.".parse().unwrap();
+        let next_board = board.try_to_move_right();
+
+        assert!(!next_board.updated);
+        assert_eq!("4,2,8,16/.,.,.,./.,.,.,./.,.,.,.", next_board.to_string());
+    }
+
+    #[test]
+    fn test_try_to_move_left_merge_huge_blocks() {
+        let board: Board = "2^40,2^40/.,.".parse().unwrap();
+        let next_board = board.try_to_move_left();
+
+        assert!(next_board.updated);
+        assert_eq!("2^41,./.,.", next_board.to_string());
+        assert_eq!(next_board.score, 1 << 41);
+    }
 
+    #[test]
+    fn test_try_to_move_with_fibonacci_rule() {
+        // ranks 1, 1, 2, 3 are the Fibonacci blocks 1, 1, 2, 3
+        let board: Board = "2,2,4,8".parse().unwrap();
+        let next_board = board.try_to_move_with(Move::Left, &Fibonacci);
+
+        // 1 + 1 = 2, then 2 + 3 = 5
+        assert_eq!(vec![vec![2, 4, 0, 0]], next_board.ranks());
+        assert_eq!(7, next_board.score);
+        assert!(!"4,16".parse::<Board>().unwrap().can_move_with(&Fibonacci));
+        assert!("4,8".parse::<Board>().unwrap().can_move_with(&Fibonacci));
+    }
+
+    #[test]
+    fn test_try_to_move_with_threes_rule() {
+        // ranks 1, 2 and 3 are the Threes blocks 1, 2 and 3
         let board = Board {
-            width: 4,
-            height: 4,
-            blocks: blocks,
-            updated: false
+            blocks: tiles(vec![vec![1, 0, 2, 3], vec![1, 2, 3, 3], vec![3, 3, 3, 0]]),
+            ..Board::new(4, 3)
         };
-        let next_board = board.try_to_move_right();
+        let next_board = board.try_to_move_with(Move::Left, &Threes);
+
+        // every line moves one step at most, merging at most once
+        assert_eq!(
+            vec![vec![1, 2, 3, 0], vec![3, 3, 3, 0], vec![4, 3, 0, 0]],
+            next_board.ranks()
+        );
+        assert_eq!(3 + 6, next_board.score);
+        assert_eq!(vec![(0, 3), (1, 3), (2, 3)], next_board.entry_indexes(&board, Move::Left));
+
+        let stuck = Board {
+            blocks: tiles(vec![vec![1, 3, 2, 4]]),
+            ..Board::new(4, 1)
+        };
+        assert!(!stuck.try_to_move_with(Move::Right, &Threes).updated);
+        assert!(stuck.entry_indexes(&stuck, Move::Right).is_empty());
+    }
 
-        assert!(!next_board.updated);
-        assert_eq!(next_board.blocks, expected_blocks);
+    #[test]
+    fn test_try_to_move_on_hex_board() {
+        // a hexagon of radius 1 in a 3x3 square, whose corners (0, 0) and
+        // (2, 2) are not part of it
+        let mut board = Board::with_topology(3, 3, Topology::Hex);
+        board.blocks[0][2] = Tile::Block(1);
+        board.blocks[1][1] = Tile::Block(1);
+
+        let next_board = board.try_to_move(Move::DownLeft);
+        assert_eq!(Tile::Block(2), next_board.blocks[2][0]);
+        assert_eq!(4, next_board.score);
+
+        // (2, 2) is not part of the board, so the first block stops above it
+        let next_board = board.try_to_move(Move::Down);
+        assert_eq!(vec![vec![0, 0, 0], vec![0, 0, 1], vec![0, 1, 0]], next_board.ranks());
+        assert!(!board.try_to_move(Move::Left).updated);
+        assert_eq!(5, board.empty_indexes().len());
+    }
+
+    #[test]
+    fn test_try_to_move_on_torus() {
+        let mut board = Board::with_topology(4, 2, Topology::Torus);
+        board.blocks = tiles(vec![vec![1, 2, 0, 1], vec![0, 1, 1, 1]]);
+
+        // the 2 on the left edge passes through it and merges with the 2 on
+        // the right one, behind the 4
+        let next_board = board.try_to_move(Move::Left);
+        assert_eq!(vec![vec![2, 2, 0, 0], vec![1, 2, 0, 0]], next_board.ranks());
+        assert_eq!(8, next_board.score);
+
+        // blocks still merge across the edge when nothing else moves
+        board.blocks = tiles(vec![vec![1, 2, 3, 1], vec![2, 3, 1, 3]]);
+        assert!(board.try_to_move(Move::Right).updated);
+        assert!(!board.try_to_move(Move::Up).updated);
+        assert!(board.can_move());
+
+        board.blocks[0][3] = Tile::Block(4);
+        assert!(!board.can_move());
+    }
+
+    #[test]
+    fn test_try_to_move_with_walls() {
+        let layout: Layout = "..X./..._/....".parse().unwrap();
+        let mut board = layout.board();
+        board.blocks = tiles(vec![vec![0, 1, 0, 1], vec![0; 4], vec![1, 0, 1, 0]]);
+
+        // the wall keeps the 2s of the first row apart
+        let next_board = board.try_to_move(Move::Left);
+        assert_eq!(vec![1, 0, 0, 1], next_board.ranks()[0]);
+        assert_eq!(vec![2, 0, 0, 0], next_board.ranks()[2]);
+        assert_eq!(layout.board().mask, next_board.mask);
+        assert_eq!(1, board.try_to_move(Move::Up).blocks[1][2].rank());
+
+        // blocks cannot cross the hole, which is no empty place either
+        assert_eq!(1, board.try_to_move(Move::Down).blocks[0][3].rank());
+        assert_eq!(6, board.empty_indexes().len());
+        assert!(!board.empty_indexes().contains(&(0, 2)));
+    }
+
+    #[test]
+    fn test_try_to_move_with_special_tiles() {
+        let mut board: Board = "2,#3,2,2/-4,.,4,2/*,4,.,.".parse().unwrap();
+
+        // the rock keeps the first 2 apart, the negative 4 cancels out and
+        // the wildcard doubles the 4
+        let next_board = board.try_to_move(Move::Left);
+        assert_eq!("2,#3,4,./2,.,.,./8,.,.,.", next_board.to_string());
+        assert_eq!(4 + 8, next_board.score);
+        assert!(next_board.can_move());
+
+        // the bomb clears the places around the block it makes
+        let board_with_bomb: Board = "2,4,.,./.,4!,2,./.,8,.,.".parse().unwrap();
+        let next_board = board_with_bomb.try_to_move(Move::Up);
+        assert_eq!(".,8,.,./.,.,.,./.,.,.,.", next_board.to_string());
+
+        board.erode_rocks();
+        assert_eq!(Tile::Rock(2), board.blocks[0][1]);
+        board.erode_rocks();
+        board.erode_rocks();
+        assert!(board.blocks[0][1].is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_transpose_and_flips() {
+        let board = Board {
+            updated: true,
+            score: 8,
+            ..Board::from_str("2,4,8/16,32,64").unwrap()
+        };
+
+        assert_eq!("2,16/4,32/8,64", board.transpose().to_string());
+        assert_eq!(board.transpose().width, 2);
+        assert_eq!(board.transpose().height, 3);
+        assert_eq!("8,4,2/64,32,16", board.flip_horizontal().to_string());
+        assert_eq!("16,32,64/2,4,8", board.flip_vertical().to_string());
+    }
+
+    #[test]
+    fn test_rotate() {
+        let board: Board = "2,4,8/16,32,64".parse().unwrap();
+
+        assert_eq!("16,2/32,4/64,8", board.rotate_cw().to_string());
+        assert_eq!("8,64/4,32/2,16", board.rotate_ccw().to_string());
+        assert_eq!(board.rotate_cw().rotate_ccw(), board);
+        assert_eq!(
+            board.rotate_cw().rotate_cw().rotate_cw().rotate_cw(),
+            board
+        );
+    }
+
+    #[test]
+    fn test_symmetries() {
+        let square: Board = "2,4/8,16".parse().unwrap();
+        let mut blocks = square
+            .symmetries()
+            .into_iter()
+            .map(|board| board.blocks)
+            .collect::<Vec<_>>();
+        blocks.sort();
+        blocks.dedup();
+
+        assert_eq!(8, blocks.len());
+
+        let rectangle: Board = "2,4,8/16,32,64".parse().unwrap();
+        let rotations = rectangle
+            .symmetries()
+            .into_iter()
+            .map(|board| board.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["2,4,8/16,32,64", "64,32,16/8,4,2"], rotations);
+    }
+
+    #[test]
+    fn test_transforms_keep_topology_and_walls() {
+        let mut board = "..X/.._".parse::<Layout>().unwrap().board();
+        board.blocks[0][0] = Tile::Block(1);
+
+        let turned = board.rotate_cw();
+        assert_eq!("../../_X", turned.mask.as_ref().unwrap().to_string());
+        assert_eq!(Tile::Block(1), turned.blocks[0][1]);
+        assert_eq!(board, turned.rotate_ccw());
+
+        let torus = Board::with_topology(3, 3, Topology::Torus);
+        assert_eq!(Topology::Torus, torus.flip_horizontal().topology);
+        assert_eq!(Topology::Torus, torus.canonical().topology);
+    }
+
+    #[test]
+    fn test_spawn_probabilities() {
+        let board: Board = ".,2/.,.".parse().unwrap();
+
+        let third = 1.0 / 3.0;
+        assert_eq!(
+            vec![vec![third, 0.0], vec![third, third]],
+            board.spawn_probabilities()
+        );
+        assert_eq!(
+            vec![vec![0.0, 0.0], vec![0.5, 0.5]],
+            board.put_new_block(0, 0, 1).spawn_probabilities()
+        );
+    }
+
+    #[test]
+    fn test_notation() {
+        let board: Board = "2,.,.,4/.,2^20,.,.".parse().unwrap();
+
+        assert_eq!(4, board.width);
+        assert_eq!(2, board.height);
+        assert_eq!(vec![vec![1, 0, 0, 2], vec![0, 20, 0, 0]], board.ranks());
+        assert_eq!("2,.,.,4/.,2^20,.,.", board.to_string());
+        assert_eq!(board, board.to_string().parse().unwrap());
+        assert_eq!(board, " 2, ., ., 4 / ., 2^20, ., . ".parse().unwrap());
+    }
+
+    #[test]
+    fn test_notation_rejects_bad_boards() {
+        assert!("2,./.".parse::<Board>().is_err());
+        assert!("3,.".parse::<Board>().is_err());
+        assert!("2,,.".parse::<Board>().is_err());
+        assert!("".parse::<Board>().is_err());
+    }
+
+    #[test]
+    fn test_notation_rejects_blocks_past_the_largest() {
+        // two 2^63 blocks would merge past what a u64 holds
+        assert!("9223372036854775808,9223372036854775808".parse::<Board>().is_err());
+        assert!("2^62,2^62".parse::<Board>().is_ok());
+    }
+
+    #[test]
+    fn test_try_to_move_never_merges_past_the_largest_block() {
+        let board: Board = "2^62,2^62".parse().unwrap();
+
+        assert!(!board.try_to_move_left().updated);
+    }
+
+    #[test]
+    fn test_canonical() {
+        let board: Board = ".,.,8/.,2,./4,.,.".parse().unwrap();
+
+        for symmetry in board.symmetries() {
+            assert_eq!(board.canonical(), symmetry.canonical());
+        }
+        assert_eq!(".,.,4/.,2,./8,.,.", board.canonical().to_string());
+    }
+}
@@ -0,0 +1,102 @@
+use failure::{bail, format_err, Error};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Command line options of the form `--name value` or a bare `--name`.
+pub struct Options {
+    values: HashMap<String, Option<String>>,
+}
+
+impl Options {
+    /// Parses `args`, rejecting any option not in `known` so that a typo is
+    /// not silently ignored.
+    pub fn parse(args: &[String], known: &[&str]) -> Result<Options, Error> {
+        let mut values = HashMap::new();
+        let mut args = args.iter().peekable();
+
+        while let Some(arg) = args.next() {
+            let name = match arg.strip_prefix("--") {
+                Some(name) if !name.is_empty() => name,
+                _ => bail!("unexpected argument: {}", arg),
+            };
+            if !known.contains(&name) {
+                let known = known
+                    .iter()
+                    .map(|name| format!("--{}", name))
+                    .collect::<Vec<_>>();
+
+                bail!("unknown option --{}, expected one of {}", name, known.join(", "));
+            }
+            let value = match args.peek() {
+                Some(value) if !value.starts_with("--") => args.next().cloned(),
+                _ => None,
+            };
+
+            values.insert(name.to_string(), value);
+        }
+
+        Ok(Options { values })
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn string(&self, name: &str) -> Option<&str> {
+        self.values.get(name).and_then(|value| value.as_deref())
+    }
+
+    pub fn get<T: FromStr>(&self, name: &str, default: T) -> Result<T, Error> {
+        match self.values.get(name) {
+            None => Ok(default),
+            Some(None) => bail!("--{} needs a value", name),
+            Some(Some(value)) => value
+                .parse()
+                .map_err(|_| format_err!("invalid value for --{}: {}", name, value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN: &[&str] = &["games", "threads", "json", "strategy"];
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_values_and_flags() {
+        let options = Options::parse(&args("--games 10 --json --strategy greedy"), KNOWN).unwrap();
+
+        assert_eq!(10, options.get("games", 1).unwrap());
+        assert_eq!(8, options.get("threads", 8).unwrap());
+        assert_eq!(Some("greedy"), options.string("strategy"));
+        assert!(options.flag("json"));
+        assert!(!options.flag("csv"));
+    }
+
+    #[test]
+    fn test_parse_rejects_positional_argument() {
+        assert!(Options::parse(&args("games 10"), KNOWN).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_option() {
+        let error = Options::parse(&args("--gaems 100"), KNOWN).err().unwrap();
+
+        assert_eq!(
+            "unknown option --gaems, expected one of --games, --threads, --json, --strategy",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn test_get_rejects_invalid_value() {
+        let options = Options::parse(&args("--games ten"), KNOWN).unwrap();
+
+        assert!(options.get("games", 1).is_err());
+    }
+}
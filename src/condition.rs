@@ -0,0 +1,110 @@
+use crate::game::Game;
+use std::time::Duration;
+
+/// What a game has to achieve to be won.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WinCondition {
+    /// Reach the goal block of the game, the classic rule.
+    #[default]
+    Goal,
+    /// Score at least this many points.
+    Score(u64),
+    /// Still be able to move when the clock reaches this time, scoring as
+    /// much as possible on the way.
+    Survive(Duration),
+    /// Never won by the game itself, for games whose end is decided outside,
+    /// like puzzles.
+    Never,
+}
+
+impl WinCondition {
+    pub fn met(self, game: &Game) -> bool {
+        match self {
+            WinCondition::Goal => !game.zen && game.reached_goal(),
+            WinCondition::Score(score) => game.score >= score,
+            WinCondition::Survive(time) => game.elapsed >= time,
+            WinCondition::Never => false,
+        }
+    }
+}
+
+/// What loses a game besides running out of moves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoseCondition {
+    /// The clock reached this time before the game was won.
+    TimeLimit(Duration),
+    /// This many moves were played before the game was won.
+    MoveLimit(u32),
+}
+
+impl LoseCondition {
+    pub fn met(self, game: &Game) -> bool {
+        match self {
+            LoseCondition::TimeLimit(time) => game.elapsed >= time,
+            LoseCondition::MoveLimit(moves) => game.moves >= moves,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Move;
+
+    fn game(notation: &str) -> Game {
+        Game::from_position(32, notation.parse().unwrap(), 0)
+    }
+
+    #[test]
+    fn test_score_target() {
+        let mut game = game("2,2,.,./.,.,.,./.,.,.,./.,.,.,.").winning(WinCondition::Score(4));
+
+        assert!(!game.win());
+        game.play(Move::Left);
+        assert!(game.win());
+        assert!(!game.lose());
+    }
+
+    #[test]
+    fn test_move_limit() {
+        let mut game = game("2,.,.,./.,.,.,./.,.,.,./.,.,.,.").losing(LoseCondition::MoveLimit(2));
+
+        game.play(Move::Right);
+        assert!(!game.lose());
+        game.play(Move::Left);
+        assert!(game.lose());
+        assert_eq!(Some(0), game.moves_left());
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let mut game = game("2,.,.,./.,.,.,./.,.,.,./.,.,.,.")
+            .losing(LoseCondition::TimeLimit(Duration::from_secs(60)));
+
+        game.tick(Duration::from_secs(45));
+        assert!(!game.lose());
+        assert_eq!(Some(Duration::from_secs(15)), game.time_left());
+
+        game.tick(Duration::from_secs(45));
+        assert!(game.lose());
+        assert_eq!(Some(Duration::from_secs(0)), game.time_left());
+
+        // the clock stops once the game is over
+        game.tick(Duration::from_secs(45));
+        assert_eq!(Duration::from_secs(90), game.elapsed);
+    }
+
+    #[test]
+    fn test_survival() {
+        let mut game = game("2,.,.,./.,.,.,./.,.,.,./.,.,.,.")
+            .winning(WinCondition::Survive(Duration::from_secs(30)));
+
+        game.tick(Duration::from_secs(20));
+        assert!(!game.win());
+        game.tick(Duration::from_secs(20));
+        assert!(game.win());
+
+        let stuck = self::game("2,4/4,2").winning(WinCondition::Survive(Duration::from_secs(30)));
+        assert!(stuck.lose());
+    }
+}
@@ -0,0 +1,448 @@
+use crate::block;
+use crate::board::Board;
+use crate::cli::Options;
+use crate::game::Game;
+use crate::zone;
+use failure::{bail, format_err, Error};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const USAGE: &str = "usage: tui-2048 daily [--date YYYY-MM-DD] [--share] [--results FILE]";
+
+const OPTIONS: &[&str] = &["date", "share", "results", "help"];
+
+/// Every daily challenge is a plain 4x4 game to 2048.
+pub const GOAL: u64 = 2048;
+pub const SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// The local date, so that a new challenge starts at midnight wherever
+    /// the player is.
+    pub fn today() -> Date {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs() as i64);
+
+        Date::from_days((seconds + zone::local_offset(seconds)).div_euclid(86_400))
+    }
+
+    /// The date `days` days after 1970-01-01.
+    fn from_days(days: i64) -> Date {
+        let (year, month, day) = zone::civil_from_days(days);
+
+        Date { year, month, day }
+    }
+
+    /// Seed of the day's challenge, the same for everyone playing that day.
+    pub fn seed(self) -> u64 {
+        // splitmix64, so neighbouring days get unrelated seeds
+        let mut seed =
+            (self.year as u64) * 10_000 + u64::from(self.month) * 100 + u64::from(self.day);
+        seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        seed = (seed ^ (seed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        seed = (seed ^ (seed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        seed ^ (seed >> 31)
+    }
+
+    /// The day's challenge. New blocks have the same values in the same
+    /// order for everyone, whatever moves they play, see `Game::fair`.
+    pub fn game(self) -> Game {
+        Game::with_seed(GOAL, SIZE, SIZE, self.seed()).fair()
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Date, Error> {
+        let invalid = || format_err!("invalid date `{}`, expected YYYY-MM-DD", text);
+        let parts = text
+            .split('-')
+            .map(str::parse)
+            .collect::<Result<Vec<u32>, _>>()
+            .map_err(|_| invalid())?;
+
+        match parts[..] {
+            [year, month, day]
+                if (1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day) =>
+            {
+                Ok(Date {
+                    year: year as i32,
+                    month,
+                    day,
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Number of days in `month` of `year`, from 1 for January.
+fn days_in_month(year: u32, month: u32) -> u32 {
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// How a daily challenge ended.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayResult {
+    pub date: Date,
+    pub score: u64,
+    pub moves: u32,
+    pub board: Board,
+}
+
+impl DayResult {
+    pub fn new(date: Date, game: &Game) -> DayResult {
+        DayResult {
+            date,
+            score: game.score,
+            moves: game.moves,
+            board: game.board.clone(),
+        }
+    }
+
+    /// A summary to paste into chat, with the final board drawn in emoji.
+    pub fn share(&self) -> String {
+        let rows = self
+            .board
+            .blocks
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|tile| emoji(tile.rank()))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+
+        format!(
+            "tui-2048 daily {}\nscore {} · max {} · {} moves\n{}",
+            self.date,
+            self.score,
+            block::value(self.board.max_exponent()),
+            self.moves,
+            rows.join("\n")
+        )
+    }
+}
+
+fn emoji(exponent: u8) -> char {
+    match exponent {
+        0 => '⬜',
+        1..=2 => '🟨',
+        3..=4 => '🟧',
+        5..=6 => '🟥',
+        7..=8 => '🟪',
+        9..=10 => '🟦',
+        _ => '🟩',
+    }
+}
+
+/// Results of past daily challenges, one line per day:
+///
+/// ```text
+/// 2026-10-19 score=12345 moves=789 board=2,4,8,16/...
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct Results {
+    pub days: Vec<DayResult>,
+}
+
+impl Results {
+    /// Where results are kept unless another file is given:
+    /// `$XDG_DATA_HOME/tui-2048/daily` or `~/.local/share/tui-2048/daily`.
+    pub fn default_path() -> Result<PathBuf, Error> {
+        let data = match env::var_os("XDG_DATA_HOME") {
+            Some(data) => PathBuf::from(data),
+            None => {
+                let home = env::var_os("HOME").ok_or_else(|| format_err!("HOME is not set"))?;
+
+                Path::new(&home).join(".local").join("share")
+            }
+        };
+
+        Ok(data.join("tui-2048").join("daily"))
+    }
+
+    pub fn get(&self, date: Date) -> Option<&DayResult> {
+        self.days.iter().find(|result| result.date == date)
+    }
+
+    /// Records `result`, keeping the better score when the day was already
+    /// played.
+    pub fn record(&mut self, result: DayResult) {
+        match self.days.iter_mut().find(|day| day.date == result.date) {
+            Some(day) if day.score >= result.score => {}
+            Some(day) => *day = result,
+            None => {
+                self.days.push(result);
+                self.days.sort_by_key(|day| day.date);
+            }
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Results, Error> {
+        let mut results = Results::default();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || format_err!("line {}: invalid result `{}`", number + 1, line);
+            let mut parts = line.split_whitespace();
+            let date = parts.next().unwrap_or("").parse().map_err(|_| invalid())?;
+            let (mut score, mut moves, mut board) = (None, None, None);
+
+            for part in parts {
+                let mut pair = part.splitn(2, '=');
+                match (pair.next(), pair.next()) {
+                    (Some("score"), Some(value)) => score = value.parse().ok(),
+                    (Some("moves"), Some(value)) => moves = value.parse().ok(),
+                    (Some("board"), Some(value)) => board = value.parse().ok(),
+                    _ => return Err(invalid()),
+                }
+            }
+
+            results.record(DayResult {
+                date,
+                score: score.ok_or_else(invalid)?,
+                moves: moves.ok_or_else(invalid)?,
+                board: board.ok_or_else(invalid)?,
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Reads the results at `path`; a missing file holds no results.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Results, Error> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Ok(Results::default());
+        }
+
+        let text = fs::read_to_string(path)
+            .map_err(|e| format_err!("cannot read {}: {}", path.display(), e))?;
+
+        Results::parse(&text).map_err(|e| format_err!("{}: {}", path.display(), e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format_err!("cannot create {}: {}", parent.display(), e))?;
+        }
+        fs::write(path, self.to_string())
+            .map_err(|e| format_err!("cannot write {}: {}", path.display(), e))
+    }
+}
+
+impl fmt::Display for Results {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for day in &self.days {
+            writeln!(
+                f,
+                "{} score={} moves={} board={}",
+                day.date, day.score, day.moves, day.board
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Shows past results, or the share string of one day with `--share`.
+pub fn main(args: &[String]) -> Result<(), Error> {
+    let options = Options::parse(args, OPTIONS)?;
+
+    if options.flag("help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let path = match options.string("results") {
+        Some(path) => PathBuf::from(path),
+        None => Results::default_path()?,
+    };
+    let results = Results::load(&path)?;
+    let date = match options.string("date") {
+        Some(date) => date.parse()?,
+        None => Date::today(),
+    };
+
+    if options.flag("share") {
+        match results.get(date) {
+            Some(result) => println!("{}", result.share()),
+            None => bail!("the challenge of {} has not been played", date),
+        }
+        return Ok(());
+    }
+
+    for day in &results.days {
+        println!(
+            "{}  score {:>7}  max {:>5}  {:>5} moves",
+            day.date,
+            day.score,
+            block::value(day.board.max_exponent()),
+            day.moves
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Move;
+
+    fn date(text: &str) -> Date {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_date() {
+        let day = date("2026-10-19");
+
+        assert_eq!(
+            Date {
+                year: 2026,
+                month: 10,
+                day: 19
+            },
+            day
+        );
+        assert_eq!("2026-10-19", day.to_string());
+        assert!(date("2026-10-19") < date("2026-11-01"));
+        assert!("2026-13-01".parse::<Date>().is_err());
+        assert!("2024-02-31".parse::<Date>().is_err());
+        assert!("2026-04-31".parse::<Date>().is_err());
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("1900-02-29".parse::<Date>().is_err());
+        assert!("2024-02-29".parse::<Date>().is_ok());
+        assert!("2000-02-29".parse::<Date>().is_ok());
+        assert!("2026-10".parse::<Date>().is_err());
+        assert!("today".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn test_from_days() {
+        assert_eq!(date("1970-01-01"), Date::from_days(0));
+        assert_eq!(date("1969-12-31"), Date::from_days(-1));
+        assert_eq!(date("2000-03-01"), Date::from_days(11_017));
+        assert_eq!(date("2024-02-29"), Date::from_days(19_782));
+        assert_eq!(date("2026-10-19"), Date::from_days(20_745));
+    }
+
+    #[test]
+    fn test_everyone_plays_the_same_game() {
+        let day = date("2026-10-19");
+
+        assert_eq!(day.game().board, day.game().board);
+        assert_ne!(day.seed(), date("2026-10-20").seed());
+
+        // players moving differently still get the same values
+        let values = |directions: [Move; 2]| {
+            let mut game = day.game();
+            let mut values = Vec::new();
+
+            for &direction in directions.iter().cycle().take(20) {
+                if game.play(direction) {
+                    let (row, col) = game.spawned.unwrap();
+                    values.push(game.board.blocks[row][col].rank());
+                }
+            }
+
+            values
+        };
+        let sideways = values([Move::Left, Move::Right]);
+        let upright = values([Move::Up, Move::Down]);
+        let length = sideways.len().min(upright.len());
+
+        assert!(length > 5);
+        assert_eq!(sideways[..length], upright[..length]);
+    }
+
+    #[test]
+    fn test_share() {
+        let result = DayResult {
+            date: date("2026-10-19"),
+            score: 1234,
+            moves: 120,
+            board: "2,8,32,./128,512,2048,.".parse().unwrap(),
+        };
+
+        assert_eq!(
+            "tui-2048 daily 2026-10-19\nscore 1234 · max 2048 · 120 moves\n🟨🟧🟥⬜\n🟪🟦🟩⬜",
+            result.share()
+        );
+    }
+
+    #[test]
+    fn test_results() {
+        let day = |text: &str, score| DayResult {
+            date: date(text),
+            score,
+            moves: 10,
+            board: "2,4/.,.".parse().unwrap(),
+        };
+        let mut results = Results::default();
+
+        results.record(day("2026-10-19", 100));
+        results.record(day("2026-10-18", 50));
+        results.record(day("2026-10-19", 80));
+        assert_eq!(100, results.get(date("2026-10-19")).unwrap().score);
+        results.record(day("2026-10-19", 120));
+        assert_eq!(120, results.get(date("2026-10-19")).unwrap().score);
+
+        assert_eq!(date("2026-10-18"), results.days[0].date);
+        assert_eq!(results, Results::parse(&results.to_string()).unwrap());
+        assert!(Results::parse("2026-10-19 score=1").is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = env::temp_dir()
+            .join(format!("tui-2048-daily-{}", std::process::id()))
+            .join("daily");
+        let mut results = Results::default();
+        results.record(DayResult::new(
+            date("2026-10-19"),
+            &date("2026-10-19").game(),
+        ));
+
+        assert_eq!(Results::default(), Results::load(&path).unwrap());
+        results.save(&path).unwrap();
+        assert_eq!(results, Results::load(&path).unwrap());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,212 @@
+use crate::block::{SpawnRule, MAX_EXPONENT};
+use crate::board::{Board, Move};
+use crate::tile::Tile;
+use failure::{bail, Error};
+use rand::Rng;
+
+/// Largest width or height the editor resizes a board to.
+pub const MAX_SIZE: usize = 16;
+
+/// Sets up a board block by block, for puzzles and bug reports.
+pub struct Editor {
+    pub board: Board,
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Editor {
+    pub fn new(board: Board) -> Editor {
+        Editor {
+            board: Board {
+                updated: false,
+                score: 0,
+                ..board
+            },
+            row: 0,
+            col: 0,
+        }
+    }
+
+    pub fn move_cursor(&mut self, direction: Move) {
+        match direction {
+            Move::Up => self.row = self.row.saturating_sub(1),
+            Move::Down => self.row = (self.row + 1).min(self.board.height - 1),
+            Move::Left => self.col = self.col.saturating_sub(1),
+            Move::Right => self.col = (self.col + 1).min(self.board.width - 1),
+            _ => {}
+        }
+    }
+
+    /// Doubles the block under the cursor, or puts a 2 when it is empty.
+    pub fn raise(&mut self) {
+        let block = &mut self.board.blocks[self.row][self.col];
+
+        *block = Tile::from((block.rank() + 1).min(MAX_EXPONENT));
+    }
+
+    /// Halves the block under the cursor, clearing it when it is a 2.
+    pub fn lower(&mut self) {
+        let block = &mut self.board.blocks[self.row][self.col];
+
+        *block = Tile::from(block.rank().saturating_sub(1));
+    }
+
+    pub fn clear(&mut self) {
+        self.board.blocks[self.row][self.col] = Tile::Empty;
+    }
+
+    pub fn clear_all(&mut self) {
+        self.board = Board::new(self.board.width, self.board.height);
+    }
+
+    /// Puts a new block, drawn by `spawn_rule`, on every empty block.
+    pub fn fill_randomly<R: Rng + ?Sized>(&mut self, spawn_rule: SpawnRule, rng: &mut R) {
+        for (row, col) in self.board.empty_indexes() {
+            self.board.blocks[row][col] = Tile::from(spawn_rule.pick(rng));
+        }
+    }
+
+    /// Changes the size of the board by the given amounts, keeping the blocks
+    /// which still fit and clamping the size to `1..=MAX_SIZE`.
+    pub fn resize(&mut self, width: isize, height: isize) {
+        let clamp = |size: usize, delta: isize| {
+            (size as isize + delta).max(1).min(MAX_SIZE as isize) as usize
+        };
+        let width = clamp(self.board.width, width);
+        let height = clamp(self.board.height, height);
+        let mut board = Board::new(width, height);
+
+        for (i, row) in self.board.blocks.iter().take(height).enumerate() {
+            for (j, &block) in row.iter().take(width).enumerate() {
+                board.blocks[i][j] = block;
+            }
+        }
+
+        self.board = board;
+        self.row = self.row.min(height - 1);
+        self.col = self.col.min(width - 1);
+    }
+
+    /// The edited board, if a game can be played from it.
+    pub fn validate(&self) -> Result<Board, Error> {
+        let board = &self.board;
+
+        if board.blocks.len() != board.height
+            || board.blocks.iter().any(|row| row.len() != board.width)
+        {
+            bail!("the blocks do not match the size of the board");
+        }
+        if board
+            .blocks
+            .iter()
+            .flatten()
+            .any(|&block| block.rank() > MAX_EXPONENT)
+        {
+            bail!("blocks must not be larger than 2^{}", MAX_EXPONENT);
+        }
+        if board.empty_indexes().len() == board.width * board.height {
+            bail!("the board is empty");
+        }
+        if !board.can_move() {
+            bail!("no move is possible on this board");
+        }
+
+        Ok(board.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn editor(notation: &str) -> Editor {
+        Editor::new(notation.parse().unwrap())
+    }
+
+    #[test]
+    fn test_cursor_stays_on_the_board() {
+        let mut editor = editor(".,./.,./.,.");
+
+        editor.move_cursor(Move::Up);
+        editor.move_cursor(Move::Left);
+        assert_eq!((0, 0), (editor.row, editor.col));
+
+        for _ in 0..5 {
+            editor.move_cursor(Move::Down);
+            editor.move_cursor(Move::Right);
+        }
+        assert_eq!((2, 1), (editor.row, editor.col));
+    }
+
+    #[test]
+    fn test_raise_lower_and_clear() {
+        let mut editor = editor(".,./.,.");
+
+        editor.raise();
+        editor.raise();
+        assert_eq!("4,./.,.", editor.board.to_string());
+
+        editor.lower();
+        assert_eq!("2,./.,.", editor.board.to_string());
+
+        editor.lower();
+        editor.lower();
+        assert_eq!(".,./.,.", editor.board.to_string());
+
+        editor.move_cursor(Move::Right);
+        editor.raise();
+        editor.clear();
+        assert_eq!(".,./.,.", editor.board.to_string());
+
+        for _ in 0..100 {
+            editor.raise();
+        }
+        assert_eq!(62, editor.board.blocks[0][1].rank());
+    }
+
+    #[test]
+    fn test_fill_randomly() {
+        let mut editor = editor("8,./.,.");
+
+        editor.fill_randomly(SpawnRule::default(), &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(3, editor.board.blocks[0][0].rank());
+        assert!(editor.board.empty_indexes().is_empty());
+
+        editor.clear_all();
+        assert_eq!(".,./.,.", editor.board.to_string());
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut editor = editor("2,4/8,16");
+        editor.move_cursor(Move::Down);
+        editor.move_cursor(Move::Right);
+
+        editor.resize(1, -1);
+        assert_eq!("2,4,.", editor.board.to_string());
+        assert_eq!((0, 1), (editor.row, editor.col));
+
+        editor.resize(-10, 20);
+        assert_eq!(1, editor.board.width);
+        assert_eq!(MAX_SIZE, editor.board.height);
+        assert_eq!(1, editor.board.blocks[0][0].rank());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(editor("2,./.,.").validate().is_ok());
+        assert!(editor(".,./.,.").validate().is_err());
+        assert!(editor("2,4/4,2").validate().is_err());
+
+        let mut broken = editor("2,./.,.");
+        broken.board.width = 3;
+        assert!(broken.validate().is_err());
+
+        let mut huge = editor("2,./.,.");
+        huge.board.blocks[0][0] = Tile::Block(63);
+        assert!(huge.validate().is_err());
+    }
+}
@@ -1,12 +1,14 @@
 use std::io;
 use std::sync::mpsc;
 use std::thread;
+use std::time::Duration;
 
 use termion::event::Key;
 use termion::input::TermRead;
 
 pub enum Event<I> {
     Input(I),
+    Tick,
 }
 
 pub struct Events {
@@ -16,12 +18,14 @@ pub struct Events {
 #[derive(Debug, Clone, Copy)]
 pub struct Config {
     pub exit_key: Key,
+    pub tick_rate: Duration,
 }
 
 impl Default for Config {
     fn default() -> Config {
         Config {
             exit_key: Key::Char('q'),
+            tick_rate: Duration::from_millis(250),
         }
     }
 }
@@ -34,22 +38,24 @@ impl Events {
     pub fn with_config(config: Config) -> Events {
         let (tx, rx) = mpsc::channel();
         
-        let tx = tx.clone();
+        let input = tx.clone();
         thread::spawn(move || {
             let stdin = io::stdin();
-            for evt in stdin.keys() {
-                match evt {
-                    Ok(key) => {
-                        if let Err(_) = tx.send(Event::Input(key)) {
-                            return;
-                        }
-                        if key == config.exit_key {
-                            return;
-                        }
-                    }
-                    Err(_) => {}
+            for key in stdin.keys().flatten() {
+                if input.send(Event::Input(key)).is_err() {
+                    return;
                 }
+                if key == config.exit_key {
+                    return;
+                }
+            }
+        });
+
+        thread::spawn(move || loop {
+            if tx.send(Event::Tick).is_err() {
+                return;
             }
+            thread::sleep(config.tick_rate);
         });
             
         Events { rx }
@@ -1,85 +1,564 @@
-use crate::block::block;
-use crate::board::Board;
+use crate::block::{self, SpawnRule};
+use crate::board::{Board, Move};
+use crate::condition::{LoseCondition, WinCondition};
+use crate::merge::{Classic, MergeRule, Slide};
+use crate::position::Position;
+use crate::replay::Replay;
+use crate::spawner::{Random, Spawner};
+use crate::tile::Tile;
+use failure::{format_err, Error};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
 
+/// A new block put at a fixed place instead of a random one, written as
+/// `value@row,col`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spawn {
+    pub row: usize,
+    pub col: usize,
+    pub exponent: u8,
+}
+
+impl fmt::Display for Spawn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}@{},{}", block::label(self.exponent), self.row, self.col)
+    }
+}
+
+impl FromStr for Spawn {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Spawn, Error> {
+        let invalid = || format_err!("invalid spawn `{}`, expected `value@row,col`", text);
+        let mut parts = text.splitn(2, '@');
+        let exponent = block::parse(parts.next().unwrap_or("")).ok_or_else(invalid)?;
+        let mut index = parts.next().ok_or_else(invalid)?.splitn(2, ',');
+        let row = index.next().unwrap_or("").parse().map_err(|_| invalid())?;
+        let col = index
+            .next()
+            .ok_or_else(invalid)?
+            .parse()
+            .map_err(|_| invalid())?;
+
+        Ok(Spawn { row, col, exponent })
+    }
+}
+
+#[derive(Clone)]
 pub struct Game {
-    goal: u32,
+    /// Exponent of the block to reach.
+    goal: u8,
     pub board: Board,
     pub width: usize,
     pub height: usize,
-    pub score: u32,
+    pub score: u64,
+    pub moves: u32,
+    /// Turns the board every this many moves, for the rotating variant.
+    pub rotate_every: Option<u32>,
+    pub seed: u64,
+    pub spawn_rule: SpawnRule,
+    /// Position the game started from, when it did not start from two
+    /// random blocks.
+    pub start: Option<Position>,
+    /// New blocks to put, in order, before falling back to random ones.
+    pub spawns: Vec<Spawn>,
+    next_spawn: usize,
+    /// Moves played so far, which replay the game together with `seed`.
+    pub history: Vec<Move>,
+    pub win_condition: WinCondition,
+    /// Ways to lose besides running out of moves.
+    pub lose_conditions: Vec<LoseCondition>,
+    /// Time played so far, counted by `tick`.
+    pub elapsed: Duration,
+    /// Removes the smallest blocks instead of losing when no move is left.
+    pub zen: bool,
+    /// Times the smallest blocks were removed in zen mode.
+    pub clears: u32,
+    /// Decides which blocks merge and what they are worth.
+    pub merge_rule: &'static dyn MergeRule,
+    /// New blocks left to deal, last first, for rules which deal them.
+    deck: Vec<u8>,
+    /// Place of the block put after the last move.
+    pub spawned: Option<(usize, usize)>,
+    /// Chance of a new block being a special tile instead, see `Tile`.
+    pub specials: f64,
+    /// Decides where new blocks appear, unless they are dealt or scripted.
+    pub spawner: &'static dyn Spawner,
+    /// Leaves new blocks to a second player, who puts them with `place`.
+    pub hot_seat: bool,
+    /// Whether the second player of a hot-seat game has a block to put.
+    placing: bool,
+    /// Draws the values of new blocks apart from their places, see `fair`.
+    values: Option<StdRng>,
+    /// Empty board the game opened on, kept until the first move so that
+    /// the opening blocks follow the rules and spawner set after `with_board`.
+    opening: Option<Board>,
+    rng: StdRng,
 }
 
 impl Game {
-    pub fn new(goal: u32, width: usize, height: usize) -> Game {
-        let mut board = Board::new(width, height);
+    pub fn new(goal: u64, width: usize, height: usize) -> Game {
+        Game::with_seed(goal, width, height, rand::random())
+    }
+
+    /// Creates a game whose new blocks are drawn from a generator seeded
+    /// with `seed`, so the same seed and moves always replay identically.
+    pub fn with_seed(goal: u64, width: usize, height: usize, seed: u64) -> Game {
+        Game::with_board(goal, Board::new(width, height), seed)
+    }
+
+    /// Creates a game on `board`, an empty board of any topology, starting
+    /// with two random blocks drawn as in `with_seed`.
+    pub fn with_board(goal: u64, board: Board, seed: u64) -> Game {
+        let mut game = Game {
+            opening: Some(board.clone()),
+            ..Game::on(goal, board, seed)
+        };
+
+        game.open();
+        game
+    }
+
+    /// Creates a game which continues from `position` instead of an empty
+    /// board.
+    pub fn from_position(goal: u64, position: Position, seed: u64) -> Game {
+        let board = position.board.clone();
 
-        board = Game::put_new_block(board);
-        board = Game::put_new_block(board);
+        Game {
+            score: position.score,
+            spawn_rule: position.spawn_rule,
+            start: Some(position),
+            ..Game::on(goal, board, seed)
+        }
+    }
 
+    /// A game of the default settings on `board` as it is.
+    fn on(goal: u64, board: Board, seed: u64) -> Game {
         Game {
-            goal: goal,
-            board: board,
-            width: width,
-            height: height,
+            goal: block::exponent(goal),
+            width: board.width,
+            height: board.height,
+            board,
             score: 0,
+            moves: 0,
+            rotate_every: None,
+            seed,
+            spawn_rule: SpawnRule::default(),
+            start: None,
+            spawns: Vec::new(),
+            next_spawn: 0,
+            history: Vec::new(),
+            win_condition: WinCondition::default(),
+            lose_conditions: Vec::new(),
+            elapsed: Duration::default(),
+            zen: false,
+            clears: 0,
+            merge_rule: &Classic,
+            deck: Vec::new(),
+            spawned: None,
+            specials: 0.0,
+            spawner: &Random,
+            hot_seat: false,
+            placing: false,
+            values: None,
+            opening: None,
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 
-    fn put_new_block(board: Board) -> Board {
-        if let Some((row, col)) = board.pick_empty_index() {
-            let block = block::random();
+    /// Value of the block to reach.
+    pub fn goal(&self) -> u64 {
+        self.merge_rule.value(self.goal)
+    }
 
-            board.put_new_block(row, col, block)
-        } else {
-            board
+    /// The settings and moves of the game so far.
+    pub fn replay(&self) -> Replay {
+        Replay {
+            seed: self.seed,
+            goal: self.goal(),
+            width: self.width,
+            height: self.height,
+            rotate_every: self.rotate_every,
+            start: self.start.clone(),
+            spawns: self.spawns.clone(),
+            zen: self.zen,
+            merge_rule: self.merge_rule,
+            topology: self.board.topology,
+            layout: self.board.mask.as_deref().cloned(),
+            specials: self.specials,
+            spawner: self.spawner,
+            fair: self.values.is_some(),
+            moves: self.history.clone(),
         }
     }
 
-    pub fn win(&self) -> bool {
+    /// Makes the board turn every `moves` moves: a quarter turn clockwise
+    /// when it is square and a half turn otherwise.
+    pub fn rotating(mut self, moves: u32) -> Game {
+        self.rotate_every = Some(moves).filter(|&moves| moves > 0);
+        self
+    }
+
+    /// Puts the given blocks after the first moves. A spawn whose place is
+    /// taken is replaced by a random block.
+    pub fn with_spawns(mut self, spawns: Vec<Spawn>) -> Game {
+        self.spawns = spawns;
+        self
+    }
+
+    /// Makes blocks merge by `rule`. The goal becomes the smallest block of
+    /// the rule worth at least the current goal.
+    pub fn merging(mut self, rule: &'static dyn MergeRule) -> Game {
+        self.goal = rule.rank(self.goal());
+        self.merge_rule = rule;
+        self.deck.clear();
+        self.shuffle_deck();
+        self.open();
+        self
+    }
+
+    /// The new block which will be put after the next move, when the merge
+    /// rule deals them from a deck.
+    pub fn next_block(&self) -> Option<u8> {
+        self.deck.last().copied()
+    }
+
+    fn shuffle_deck(&mut self) {
+        self.deck = self.merge_rule.deck();
+        self.deck.shuffle(&mut self.rng);
+    }
+
+    /// Puts the two opening blocks on the empty board the game opened on,
+    /// the way new blocks are put after a move, from a fresh generator so
+    /// that the opening only depends on the seed and the final settings.
+    fn open(&mut self) {
+        let mut board = match self.opening.clone() {
+            Some(board) => board,
+            None => return,
+        };
+
+        self.rng = StdRng::seed_from_u64(self.seed);
+        if self.values.is_some() {
+            self.values = Some(StdRng::seed_from_u64(!self.seed));
+        }
+        self.shuffle_deck();
+        // opening blocks are never special tiles
+        for _ in 0..2 {
+            board = if self.deck.is_empty() {
+                match self.spawn(&board) {
+                    Some((row, col, block)) => board.put_new_block(row, col, block),
+                    None => board,
+                }
+            } else {
+                let places = board.empty_indexes();
+                self.deal(board, places)
+            };
+        }
+        self.board = board;
+    }
+
+    /// Puts a new block, dealt from the deck when the merge rule has one, on
+    /// a random place of `places`.
+    fn deal(&mut self, board: Board, places: Vec<(usize, usize)>) -> Board {
+        match places.choose(&mut self.rng).copied() {
+            Some((row, col)) => {
+                let block = match self.deck.pop() {
+                    Some(block) => {
+                        if self.deck.is_empty() {
+                            self.shuffle_deck();
+                        }
+                        block
+                    }
+                    None => self.spawn_rule.pick(&mut self.rng),
+                };
+
+                board.put_new_block(row, col, block)
+            }
+            None => board,
+        }
+    }
+
+    /// Makes the game won by reaching the smallest block worth at least
+    /// `goal` under the merge rule of the game.
+    pub fn reaching(mut self, goal: u64) -> Game {
+        self.goal = self.merge_rule.rank(goal);
+        self
+    }
+
+    /// Makes the game endless: it is neither lost when no move is left nor
+    /// won on reaching the goal block, though conditions given with
+    /// `winning` and `losing` still apply.
+    pub fn zen_mode(mut self) -> Game {
+        self.zen = true;
+        self
+    }
+
+    /// Makes a new block a special tile with a chance of `chance`: a rock, a
+    /// wildcard, a bomb or a negative block, see `Tile`.
+    pub fn with_specials(mut self, chance: f64) -> Game {
+        self.specials = chance;
+        self
+    }
+
+    /// Makes `spawner` decide where new blocks appear.
+    pub fn spawning(mut self, spawner: &'static dyn Spawner) -> Game {
+        self.spawner = spawner;
+        self.open();
+        self
+    }
+
+    /// Makes the game a hot-seat game for two players: after each move the
+    /// second player puts the new block with `place`. The blocks they put
+    /// are kept in `spawns`, so the replay plays them back.
+    pub fn hot_seat_mode(mut self) -> Game {
+        self.hot_seat = true;
+        self
+    }
+
+    /// Draws the values of new blocks from a generator of their own, so
+    /// that games on the same seed get the same values in the same order
+    /// wherever their blocks land.
+    pub fn fair(mut self) -> Game {
+        self.values = Some(StdRng::seed_from_u64(!self.seed));
+        self.open();
+        self
+    }
+
+    /// Whether it is the turn of the second player of a hot-seat game.
+    pub fn placing(&self) -> bool {
+        self.placing
+    }
+
+    /// Makes `condition` win the game instead of reaching the goal block.
+    pub fn winning(mut self, condition: WinCondition) -> Game {
+        self.win_condition = condition;
+        self
+    }
+
+    /// Makes `condition` lose the game too.
+    pub fn losing(mut self, condition: LoseCondition) -> Game {
+        self.lose_conditions.push(condition);
+        self
+    }
+
+    /// Advances the clock of the game by `delta`, unless it is over.
+    pub fn tick(&mut self, delta: Duration) {
+        if !self.win() && !self.lose() {
+            self.elapsed += delta;
+        }
+    }
+
+    /// Time until the clock ends the game, if it can.
+    pub fn time_left(&self) -> Option<Duration> {
+        let limits = self.lose_conditions.iter().filter_map(|&condition| match condition {
+            LoseCondition::TimeLimit(time) => Some(time),
+            _ => None,
+        });
+        let survive = match self.win_condition {
+            WinCondition::Survive(time) => Some(time),
+            _ => None,
+        };
+
+        limits
+            .chain(survive)
+            .min()
+            .map(|time| time.checked_sub(self.elapsed).unwrap_or_default())
+    }
+
+    /// Moves which may still be played, if they are limited.
+    pub fn moves_left(&self) -> Option<u32> {
+        self.lose_conditions
+            .iter()
+            .filter_map(|&condition| match condition {
+                LoseCondition::MoveLimit(moves) => Some(moves.saturating_sub(self.moves)),
+                _ => None,
+            })
+            .min()
+    }
+
+    /// Whether the board holds the goal block.
+    pub fn reached_goal(&self) -> bool {
         self.board.has_block_with(self.goal)
     }
 
-    pub fn lose(&self) -> bool {
-        !self.win() && !self.board.has_empty_block()
+    fn rotate(&mut self) {
+        let (width, height) = (self.width, self.height);
+
+        if width == height {
+            self.board = self.board.rotate_cw();
+            self.spawned = self.spawned.map(|(row, col)| (col, height - 1 - row));
+        } else {
+            self.board = self.board.flip_horizontal().flip_vertical();
+            self.spawned = self
+                .spawned
+                .map(|(row, col)| (height - 1 - row, width - 1 - col));
+        }
     }
 
-    pub fn move_up(&mut self) {
-        let board = self.board.try_to_move_up();
+    /// Removes every block with the smallest value, making room to move.
+    fn clear_smallest(&mut self) {
+        let smallest = self
+            .board
+            .blocks
+            .iter()
+            .flatten()
+            .map(|tile| tile.rank())
+            .filter(|&rank| rank > 0)
+            .min();
 
-        if board.updated {
-            self.board = Game::put_new_block(board);
+        if let Some(smallest) = smallest {
+            for block in self.board.blocks.iter_mut().flatten() {
+                if block.rank() == smallest {
+                    *block = Tile::Empty;
+                }
+            }
+            self.clears += 1;
         }
     }
 
-    pub fn move_down(&mut self) {
-        let board = self.board.try_to_move_down();
+    /// The place the spawner picks for a new block, and its value, drawn
+    /// apart when the game is fair.
+    fn spawn(&mut self, board: &Board) -> Option<(usize, usize, u8)> {
+        let spawn = self
+            .spawner
+            .spawn(board, self.merge_rule, self.spawn_rule, &mut self.rng);
+        let spawn_rule = self.spawn_rule;
 
-        if board.updated {
-            self.board = Game::put_new_block(board);
+        match &mut self.values {
+            Some(values) => spawn.map(|(row, col, _)| (row, col, spawn_rule.pick(values))),
+            None => spawn,
         }
     }
 
-    pub fn move_left(&mut self) {
-        let board = self.board.try_to_move_left();
+    /// Puts the block the spawner picks, or a special tile in its place
+    /// with a chance of `specials`.
+    fn put_new_block(&mut self, mut board: Board) -> Board {
+        match self.spawn(&board) {
+            // the chance is only drawn with special tiles, so that other
+            // games replay as before
+            Some((row, col, block)) if self.specials > 0.0 && self.rng.gen_bool(self.specials) => {
+                board.blocks[row][col] = Tile::random_special(block, &mut self.rng);
+                board
+            }
+            Some((row, col, block)) => board.put_new_block(row, col, block),
+            None => board,
+        }
+    }
+
+    pub fn win(&self) -> bool {
+        self.win_condition.met(self)
+    }
+
+    pub fn lose(&self) -> bool {
+        !self.win()
+            && (!self.zen && !self.board.can_move_with(self.merge_rule)
+                || self.lose_conditions.iter().any(|condition| condition.met(self)))
+    }
 
-        if board.updated {
-            self.board = Game::put_new_block(board);
+    /// Plays `direction` and returns whether it changed the board.
+    pub fn play(&mut self, direction: Move) -> bool {
+        let mut board = self.board.try_to_move_with(direction, self.merge_rule);
+
+        if self.placing || !board.updated {
+            return false;
+        }
+
+        board.erode_rocks();
+        self.score += board.score;
+        self.moves += 1;
+        self.history.push(direction);
+        self.opening = None;
+        if self.hot_seat {
+            self.board = board;
+            self.placing = true;
+            return true;
         }
+
+        let empty = board.empty_indexes();
+        self.board = match self.spawns.get(self.next_spawn) {
+            // `empty` only holds places on the board, whatever the spawn says
+            Some(spawn) if empty.contains(&(spawn.row, spawn.col)) => {
+                board.put_new_block(spawn.row, spawn.col, spawn.exponent)
+            }
+            _ if self.merge_rule.slide() == Slide::Full && self.deck.is_empty() => {
+                self.put_new_block(board)
+            }
+            _ => {
+                let places = match self.merge_rule.slide() {
+                    Slide::Full => board.empty_indexes(),
+                    Slide::OneStep => board.entry_indexes(&self.board, direction),
+                };
+
+                self.deal(board, places)
+            }
+        };
+        self.end_turn(empty);
+
+        true
     }
 
-    pub fn move_right(&mut self) {
-        let board = self.board.try_to_move_right();
+    /// Puts the block of exponent `exponent` picked by the second player of
+    /// a hot-seat game at `row`, `col`, and returns whether it could: it must
+    /// be their turn and the place must be empty.
+    pub fn place(&mut self, row: usize, col: usize, exponent: u8) -> bool {
+        if !self.placing || !self.board.contains(row, col) || !self.board.blocks[row][col].is_empty() {
+            return false;
+        }
+
+        let empty = self.board.empty_indexes();
+        self.board = self.board.clone().put_new_block(row, col, exponent);
+        self.spawns.push(Spawn { row, col, exponent });
+        self.placing = false;
+        self.end_turn(empty);
 
-        if board.updated {
-            self.board = Game::put_new_block(board);
+        true
+    }
+
+    /// Wraps up a move once its new block is put, `empty` being the places
+    /// which were empty before.
+    fn end_turn(&mut self, empty: Vec<(usize, usize)>) {
+        self.next_spawn += 1;
+        self.spawned = empty
+            .into_iter()
+            .find(|&(row, col)| !self.board.blocks[row][col].is_empty());
+
+        if let Some(every) = self.rotate_every {
+            if self.moves.is_multiple_of(every) {
+                self.rotate();
+            }
+        }
+
+        if self.zen && !self.board.can_move_with(self.merge_rule) {
+            self.clear_smallest();
         }
     }
-}
 
+    pub fn move_up(&mut self) {
+        self.play(Move::Up);
+    }
+
+    pub fn move_down(&mut self) {
+        self.play(Move::Down);
+    }
+
+    pub fn move_left(&mut self) {
+        self.play(Move::Left);
+    }
+
+    pub fn move_right(&mut self) {
+        self.play(Move::Right);
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::merge::Threes;
+    use crate::spawner::Corner;
 
     #[test]
     fn test_new() {
@@ -89,7 +568,7 @@ mod tests {
         
         let game = Game::new(goal, width, height);
 
-        assert_eq!(goal, game.goal);
+        assert_eq!(5, game.goal);
         assert_eq!(width, game.width);
         assert_eq!(height, game.height);
         assert_eq!(0, game.score);
@@ -97,7 +576,7 @@ mod tests {
         let mut num_blocks = 0;
         for i in 0..height {
             for j in 0..width {
-                if game.board.blocks[i][j] > 0 {
+                if !game.board.blocks[i][j].is_empty() {
                     num_blocks += 1;
                 }
             }
@@ -107,141 +586,240 @@ mod tests {
 
     #[test]
     fn test_win_when_there_is_block_with_goal_value() {
-        let width = 4;
-        let height = 4;
-        let goal = 32;
-        let blocks = vec![
-            vec![0, 4, 0, 32],
-            vec![0, 2, 4, 0],
-            vec![0, 0, 2, 0],
-            vec![0, 0, 0, 0],
-        ];
-        let board = Board {
-            width: width,
-            height: height,
-            blocks: blocks,
-            updated: false
-        };
-        let game = Game {
-            goal: goal,
-            board: board,
-            width: width,
-            height: height,
-            score: 0
-        };
+        let position = ".,4,.,32/.,2,4,./.,.,2,./.,.,.,.".parse().unwrap();
+        let game = Game::from_position(32, position, 0);
 
         assert!(game.win());
     }
 
     #[test]
     fn test_win_when_there_is_no_block_with_goal_value() {
-        let width = 4;
-        let height = 4;
-        let goal = 32;
-        let blocks = vec![
-            vec![0, 4, 0, 31],
-            vec![0, 2, 4, 0],
-            vec![0, 0, 2, 0],
-            vec![0, 0, 0, 0],
-        ];
-        let board = Board {
-            width: width,
-            height: height,
-            blocks: blocks,
-            updated: false
-        };
-        let game = Game {
-            goal: goal,
-            board: board,
-            width: width,
-            height: height,
-            score: 0
-        };
+        let position = ".,4,.,16/.,2,4,./.,.,2,./.,.,.,.".parse().unwrap();
+        let game = Game::from_position(32, position, 0);
 
         assert!(!game.win());
     }
 
     #[test]
     fn test_lose_when_board_is_full() {
-        let width = 4;
-        let height = 4;
-        let goal = 32;
-        let blocks = vec![
-            vec![2, 4, 8, 31],
-            vec![4, 2, 4, 2],
-            vec![8, 4, 2, 4],
-            vec![2, 8, 4, 2],
-        ];
-        let board = Board {
-            width: width,
-            height: height,
-            blocks: blocks,
-            updated: false
-        };
-        let game = Game {
-            goal: goal,
-            board: board,
-            width: width,
-            height: height,
-            score: 0
-        };
+        let position = "2,4,8,16/4,2,4,2/8,4,2,4/2,8,4,2".parse().unwrap();
+        let game = Game::from_position(32, position, 0);
 
         assert!(game.lose());
     }
 
     #[test]
     fn test_lose_when_board_has_empty_block() {
-        let width = 4;
-        let height = 4;
-        let goal = 32;
-        let blocks = vec![
-            vec![0, 4, 2, 31],
-            vec![4, 2, 4, 2],
-            vec![2, 4, 2, 4],
-            vec![4, 2, 4, 2],
-        ];
-        let board = Board {
-            width: width,
-            height: height,
-            blocks: blocks,
-            updated: false
-        };
-        let game = Game {
-            goal: goal,
-            board: board,
-            width: width,
-            height: height,
-            score: 0
-        };
+        let position = ".,4,2,16/4,2,4,2/2,4,2,4/4,2,4,2".parse().unwrap();
+        let game = Game::from_position(32, position, 0);
 
         assert!(!game.lose());
     }
 
     #[test]
     fn test_lose_when_board_is_full_but_has_goal_value() {
-        let width = 4;
-        let height = 4;
-        let goal = 32;
-        let blocks = vec![
-            vec![32, 4, 2, 31],
-            vec![4, 2, 4, 2],
-            vec![2, 4, 2, 4],
-            vec![4, 2, 4, 2],
-        ];
-        let board = Board {
-            width: width,
-            height: height,
-            blocks: blocks,
-            updated: false
-        };
-        let game = Game {
-            goal: goal,
-            board: board,
-            width: width,
-            height: height,
-            score: 0
-        };
+        let position = "32,4,2,16/4,2,4,2/2,4,2,4/4,2,4,2".parse().unwrap();
+        let game = Game::from_position(32, position, 0);
+
+        assert!(!game.lose());
+    }
+
+    #[test]
+    fn test_lose_when_board_is_full_but_can_merge() {
+        let position = "2,2,8,16/4,8,4,2/8,4,2,4/2,8,4,2".parse().unwrap();
+        let game = Game::from_position(32, position, 0);
+
+        assert!(!game.lose());
+    }
+
+    #[test]
+    fn test_with_seed_is_reproducible() {
+        let mut first = Game::with_seed(2048, 4, 4, 42);
+        let mut second = Game::with_seed(2048, 4, 4, 42);
+
+        for direction in [Move::Left, Move::Up, Move::Right, Move::Down].iter().cycle().take(20) {
+            first.play(*direction);
+            second.play(*direction);
+        }
+
+        assert_eq!(first.board, second.board);
+        assert_eq!(first.score, second.score);
+        assert_eq!(first.moves, second.moves);
+    }
+
+    #[test]
+    fn test_play_adds_merged_value_to_score() {
+        let position = "4,4,2,2/.,.,.,./.,.,.,./.,.,.,.".parse().unwrap();
+        let mut game = Game::from_position(2048, position, 0);
+
+        assert!(game.play(Move::Left));
+        assert_eq!(12, game.score);
+        assert_eq!(1, game.moves);
+    }
 
+    #[test]
+    fn test_spawn_notation() {
+        let spawn: Spawn = "4@1,3".parse().unwrap();
+
+        assert_eq!(Spawn { row: 1, col: 3, exponent: 2 }, spawn);
+        assert_eq!("4@1,3", spawn.to_string());
+        assert!("4@1".parse::<Spawn>().is_err());
+        assert!("3@1,1".parse::<Spawn>().is_err());
+    }
+
+    #[test]
+    fn test_scripted_spawns() {
+        let position = "2,2,.,./.,.,.,./.,.,.,./.,.,.,.".parse().unwrap();
+        let spawns = vec!["4@0,3".parse().unwrap(), "2@0,0".parse().unwrap()];
+        let mut game = Game::from_position(2048, position, 0).with_spawns(spawns);
+
+        game.play(Move::Left);
+        assert_eq!("4,.,.,4/.,.,.,./.,.,.,./.,.,.,.", game.board.to_string());
+        assert_eq!(Some((0, 3)), game.spawned);
+
+        // the second spawn's place is taken, so a random block is put instead
+        game.play(Move::Left);
+        assert_eq!(14, game.board.empty_indexes().len());
+        assert_eq!(3, game.board.blocks[0][0].rank());
+    }
+
+    fn first_legal_move(board: &Board) -> Move {
+        Move::ALL
+            .iter()
+            .copied()
+            .find(|&direction| board.try_to_move(direction).updated)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rotating_turns_the_board_every_few_moves() {
+        let mut plain = Game::with_seed(2048, 4, 4, 3);
+        let mut rotating = Game::with_seed(2048, 4, 4, 3).rotating(2);
+
+        for _ in 0..2 {
+            let direction = first_legal_move(&plain.board);
+
+            plain.play(direction);
+            rotating.play(direction);
+        }
+
+        assert_eq!(2, rotating.moves);
+        assert_eq!(plain.board.rotate_cw(), rotating.board);
+        assert_eq!(plain.spawned.map(|(row, col)| (col, 3 - row)), rotating.spawned);
+    }
+
+    #[test]
+    fn test_rotating_rectangular_board_turns_half() {
+        let mut plain = Game::with_seed(2048, 4, 2, 5);
+        let mut rotating = Game::with_seed(2048, 4, 2, 5).rotating(1);
+
+        let direction = first_legal_move(&plain.board);
+        plain.play(direction);
+        rotating.play(direction);
+
+        assert_eq!(plain.board.flip_horizontal().flip_vertical(), rotating.board);
+        assert_eq!(4, rotating.board.width);
+    }
+
+    #[test]
+    fn test_zen_mode_clears_the_smallest_blocks() {
+        let position = "4,8/.,2".parse().unwrap();
+        let spawns = vec!["4@1,1".parse().unwrap()];
+        let mut game = Game::from_position(8, position, 0)
+            .with_spawns(spawns)
+            .zen_mode();
+
+        assert!(!game.win());
+        assert!(game.play(Move::Left));
+        assert_eq!("4,8/.,4", game.board.to_string());
+        assert_eq!(1, game.clears);
         assert!(!game.lose());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_zen_mode_keeps_other_conditions() {
+        let position = "2,.,.,./.,.,.,./.,.,.,./.,.,.,.".parse().unwrap();
+        let mut game = Game::from_position(2048, position, 0)
+            .zen_mode()
+            .losing(LoseCondition::MoveLimit(1));
+
+        game.play(Move::Right);
+        assert!(game.lose());
+    }
+
+    #[test]
+    fn test_threes_deals_blocks_at_the_edge() {
+        let position = "2,.,.,./4,.,.,./.,.,.,./.,.,.,.".parse().unwrap();
+        let mut game = Game::from_position(2048, position, 0).merging(&Threes);
+        let mut dealt = Vec::new();
+
+        assert_eq!(3072, game.goal());
+        for _ in 0..12 {
+            let next = game.next_block().unwrap();
+            let direction = first_legal_move(&game.board);
+            let moved = game.board.try_to_move_with(direction, &Threes);
+            let entries = moved.entry_indexes(&game.board, direction);
+
+            assert!(game.play(direction));
+            // the new block entered on the edge opposite to the move
+            let new = entries
+                .iter()
+                .find(|&&(row, col)| !game.board.blocks[row][col].is_empty())
+                .unwrap();
+            assert_eq!(next, game.board.blocks[new.0][new.1].rank());
+            dealt.push(next);
+        }
+
+        dealt.sort();
+        assert_eq!(Threes.deck(), dealt);
+    }
+
+    #[test]
+    fn test_hot_seat() {
+        let position = "2,.,./.,.,./.,.,.".parse().unwrap();
+        let mut game = Game::from_position(2048, position, 0).hot_seat_mode();
+
+        assert!(game.play(Move::Right));
+        assert!(game.placing());
+        assert!(!game.play(Move::Left));
+        assert!(!game.place(0, 2, 1));
+        assert!(game.place(2, 0, 2));
+        assert!(!game.placing());
+        assert_eq!(Some((2, 0)), game.spawned);
+        assert_eq!(".,.,2/.,.,./4,.,.", game.board.to_string());
+
+        assert!(game.play(Move::Down));
+        assert!(game.place(0, 0, 1));
+
+        let positions = game.replay().positions().unwrap();
+        assert_eq!(Some(&game.board), positions.last());
+    }
+
+    #[test]
+    fn test_corner_spawner() {
+        let position = ".,.,.,2/.,.,.,./.,.,.,./.,.,.,.".parse().unwrap();
+        let mut game = Game::from_position(2048, position, 0).spawning(&Corner);
+
+        // new blocks fill the first empty place, row by row
+        for &direction in &[Move::Down, Move::Right, Move::Down] {
+            let moved = game.board.try_to_move(direction);
+
+            assert!(game.play(direction));
+            assert_eq!(Some(moved.empty_indexes()[0]), game.spawned);
+        }
+    }
+
+    #[test]
+    fn test_opening_blocks_follow_the_settings() {
+        let game = Game::with_seed(2048, 4, 4, 5).spawning(&Corner).fair();
+        let rule = SpawnRule::default();
+        let mut values = StdRng::seed_from_u64(!5);
+        let opening = Board::new(4, 4)
+            .put_new_block(0, 0, rule.pick(&mut values))
+            .put_new_block(0, 1, rule.pick(&mut values));
+
+        assert_eq!(opening.blocks, game.board.blocks);
+        assert_eq!(game.board, Game::with_seed(2048, 4, 4, 5).fair().spawning(&Corner).board);
+        assert_eq!(game.board, game.replay().positions().unwrap()[0]);
+    }
+}
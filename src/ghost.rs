@@ -0,0 +1,82 @@
+use crate::board::Move;
+use crate::game::Game;
+use crate::replay::Replay;
+
+/// A recorded game played back alongside a live one, one move for each move
+/// of the live game, to race against an earlier run. The live game starts
+/// from `game` as it was before the first move, so both boards get their
+/// first blocks from the same seed.
+#[derive(Clone)]
+pub struct Ghost {
+    pub game: Game,
+    moves: Vec<Move>,
+    next: usize,
+}
+
+impl Ghost {
+    pub fn new(replay: &Replay) -> Ghost {
+        Ghost {
+            game: replay.game(),
+            moves: replay.moves.clone(),
+            next: 0,
+        }
+    }
+
+    /// Plays the next recorded move, if one is left, and returns whether it
+    /// did.
+    pub fn step(&mut self) -> bool {
+        match self.moves.get(self.next) {
+            Some(&direction) => {
+                self.next += 1;
+                self.game.play(direction)
+            }
+            None => false,
+        }
+    }
+
+    /// Number of recorded moves played so far.
+    pub fn moves(&self) -> usize {
+        self.next
+    }
+
+    /// Number of moves recorded.
+    pub fn length(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.next == self.moves.len()
+    }
+
+    /// How many points `game` is ahead of the ghost, negative when behind.
+    pub fn lead(&self, game: &Game) -> i64 {
+        game.score as i64 - self.game.score as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step() {
+        let mut recorded = Game::with_seed(2048, 4, 4, 6);
+        for &direction in &[Move::Left, Move::Up, Move::Left, Move::Up] {
+            recorded.play(direction);
+        }
+        let replay = recorded.replay();
+        let mut ghost = Ghost::new(&replay);
+        let mut game = ghost.game.clone();
+
+        assert_eq!(recorded.history.len(), ghost.length());
+        assert_eq!(replay.game().board, game.board);
+
+        while ghost.step() {
+            game.play(Move::Right);
+        }
+
+        assert!(ghost.finished());
+        assert_eq!(recorded.board, ghost.game.board);
+        assert_eq!(game.score as i64 - recorded.score as i64, ghost.lead(&game));
+    }
+}
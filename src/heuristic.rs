@@ -0,0 +1,219 @@
+use crate::bitboard::{BitBoard, SIZE};
+use failure::{bail, format_err, Error};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Exponents are raised to this power for monotonicity, so disorder among
+/// big blocks costs more than among small ones.
+const MONOTONICITY_POWER: i32 = 4;
+
+/// Weights of the board features, as read from a weights file:
+///
+/// ```text
+/// # comments and blank lines are ignored
+/// empty = 270
+/// merges = 700
+/// monotonicity = 47
+/// smoothness = 10
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    /// Reward per empty block.
+    pub empty: f64,
+    /// Reward per pair of equal neighbours.
+    pub merges: f64,
+    /// Penalty for lines which are not sorted.
+    pub monotonicity: f64,
+    /// Penalty per exponent difference between neighbours.
+    pub smoothness: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Weights {
+        Weights {
+            empty: 270.0,
+            merges: 700.0,
+            monotonicity: 47.0,
+            smoothness: 10.0,
+        }
+    }
+}
+
+impl Weights {
+    pub const NAMES: [&'static str; 4] = ["empty", "merges", "monotonicity", "smoothness"];
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut f64> {
+        match name {
+            "empty" => Some(&mut self.empty),
+            "merges" => Some(&mut self.merges),
+            "monotonicity" => Some(&mut self.monotonicity),
+            "smoothness" => Some(&mut self.smoothness),
+            _ => None,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Weights, Error> {
+        let mut weights = Weights::default();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let name = parts.next().unwrap_or("").trim();
+            let value = parts
+                .next()
+                .and_then(|value| value.trim().parse().ok())
+                .ok_or_else(|| format_err!("line {}: expected `name = number`", number + 1))?;
+
+            match weights.get_mut(name) {
+                Some(weight) => *weight = value,
+                None => bail!("line {}: unknown weight `{}`", number + 1, name),
+            }
+        }
+
+        Ok(weights)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Weights, Error> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| format_err!("cannot read {}: {}", path.display(), e))?;
+
+        Weights::parse(&text)
+    }
+}
+
+impl fmt::Display for Weights {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "empty = {}", self.empty)?;
+        writeln!(f, "merges = {}", self.merges)?;
+        writeln!(f, "monotonicity = {}", self.monotonicity)?;
+        writeln!(f, "smoothness = {}", self.smoothness)
+    }
+}
+
+/// Scores a single row or column of exponents.
+fn evaluate_line(weights: &Weights, line: [u8; SIZE]) -> f64 {
+    let empty = line.iter().filter(|&&exponent| exponent == 0).count();
+    let blocks = line.iter().copied().filter(|&exponent| exponent > 0).collect::<Vec<u8>>();
+
+    let merges = blocks.windows(2).filter(|pair| pair[0] == pair[1]).count();
+    let smoothness: u32 = blocks
+        .windows(2)
+        .map(|pair| (pair[0] as i32 - pair[1] as i32).unsigned_abs())
+        .sum();
+
+    let mut increasing = 0.0;
+    let mut decreasing = 0.0;
+    for pair in line.windows(2) {
+        let a = (pair[0] as f64).powi(MONOTONICITY_POWER);
+        let b = (pair[1] as f64).powi(MONOTONICITY_POWER);
+
+        if a > b {
+            decreasing += a - b;
+        } else {
+            increasing += b - a;
+        }
+    }
+    let monotonicity = f64::min(increasing, decreasing);
+
+    weights.empty * empty as f64 + weights.merges * merges as f64
+        - weights.monotonicity * monotonicity
+        - weights.smoothness * smoothness as f64
+}
+
+/// Weighted evaluation of a board, with every possible row precomputed.
+pub struct Heuristic {
+    rows: Vec<f64>,
+}
+
+impl Heuristic {
+    pub fn new(weights: Weights) -> Heuristic {
+        let rows = (0..=u16::MAX)
+            .map(|row| {
+                let mut line = [0; SIZE];
+                for (j, exponent) in line.iter_mut().enumerate() {
+                    *exponent = ((row >> (4 * j)) & 0xF) as u8;
+                }
+
+                evaluate_line(&weights, line)
+            })
+            .collect();
+
+        Heuristic { rows }
+    }
+
+    /// Sums the scores of every row and column. The result is the same for
+    /// all rotations and reflections of a board.
+    pub fn evaluate(&self, board: BitBoard) -> f64 {
+        let columns = board.transpose();
+
+        (0..SIZE)
+            .map(|i| self.rows[board.row(i) as usize] + self.rows[columns.row(i) as usize])
+            .sum::<f64>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_weights() {
+        let weights = Weights::parse("# tuned\nempty = 1.5\n\nsmoothness=3\n").unwrap();
+
+        assert_eq!(1.5, weights.empty);
+        assert_eq!(3.0, weights.smoothness);
+        assert_eq!(Weights::default().merges, weights.merges);
+    }
+
+    #[test]
+    fn test_parse_weights_rejects_bad_lines() {
+        assert!(Weights::parse("empty: 1").is_err());
+        assert!(Weights::parse("corner = 1").is_err());
+    }
+
+    #[test]
+    fn test_weights_round_trip() {
+        let weights = Weights {
+            empty: 1.0,
+            merges: 2.5,
+            monotonicity: 3.0,
+            smoothness: 0.25,
+        };
+
+        assert_eq!(weights, Weights::parse(&weights.to_string()).unwrap());
+    }
+
+    #[test]
+    fn test_evaluate_line() {
+        let weights = Weights {
+            empty: 1.0,
+            merges: 10.0,
+            monotonicity: 100.0,
+            smoothness: 1000.0,
+        };
+
+        assert_eq!(4.0, evaluate_line(&weights, [0, 0, 0, 0]));
+        assert_eq!(1.0 + 10.0 * 2.0, evaluate_line(&weights, [1, 1, 1, 0]));
+        assert_eq!(-1000.0 * 2.0, evaluate_line(&weights, [1, 2, 3, 3]) - 10.0);
+        assert_eq!(-100.0 * 15.0 - 1000.0 * 2.0, evaluate_line(&weights, [1, 2, 1, 0]) - 1.0);
+    }
+
+    #[test]
+    fn test_evaluate_is_symmetric() {
+        let heuristic = Heuristic::new(Weights::default());
+        let board = BitBoard::default().set(0, 0, 5).set(0, 1, 3).set(2, 1, 3).set(3, 2, 1);
+
+        assert_eq!(heuristic.evaluate(board), heuristic.evaluate(board.transpose()));
+        assert_eq!(heuristic.evaluate(board), heuristic.evaluate(board.flip_horizontal()));
+        assert_eq!(
+            heuristic.evaluate(board),
+            heuristic.evaluate(board.transpose().flip_horizontal().transpose())
+        );
+    }
+}
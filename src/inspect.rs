@@ -0,0 +1,96 @@
+use tui_2048::analysis::{self, DirectionAnalysis};
+use tui_2048::block::{self, SpawnRule};
+use tui_2048::board::{Board, Move};
+use tui_2048::strategy::Expectimax;
+
+/// What each direction would do to the current position, and where the next
+/// block may appear after the selected one.
+pub struct Inspect {
+    directions: Vec<DirectionAnalysis>,
+    selected: Move,
+    spawn_rule: SpawnRule,
+}
+
+impl Inspect {
+    pub fn new(board: &Board, spawn_rule: SpawnRule, engine: &mut Expectimax) -> Inspect {
+        let directions = analysis::directions(board, engine);
+        let selected = directions
+            .iter()
+            .find(|analysis| analysis.legal)
+            .map_or(Move::Up, |analysis| analysis.direction);
+
+        Inspect {
+            directions,
+            selected,
+            spawn_rule,
+        }
+    }
+
+    pub fn select(&mut self, direction: Move) {
+        self.selected = direction;
+    }
+
+    fn selected(&self) -> &DirectionAnalysis {
+        self.directions
+            .iter()
+            .find(|analysis| analysis.direction == self.selected)
+            .unwrap()
+    }
+
+    pub fn table(&self) -> String {
+        self.directions
+            .iter()
+            .map(|analysis| {
+                let marker = if analysis.direction == self.selected {
+                    ">"
+                } else {
+                    " "
+                };
+
+                if !analysis.legal {
+                    return format!("{} {:<5} illegal", marker, analysis.direction);
+                }
+
+                let value = match analysis.value {
+                    Some(value) => format!("{:.0}", value),
+                    None => "-".to_string(),
+                };
+
+                format!(
+                    "{} {:<5} +{:<6} empty {:<3} value {}",
+                    marker, analysis.direction, analysis.score, analysis.empty, value
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn heatmap_title(&self) -> String {
+        let chances = self
+            .spawn_rule
+            .choices()
+            .iter()
+            .map(|&(exponent, probability)| {
+                format!("{}: {:.0}%", block::value(exponent), probability * 100.0)
+            })
+            .collect::<Vec<_>>();
+
+        format!(
+            "next block after {} ({})",
+            self.selected,
+            chances.join(", ")
+        )
+    }
+
+    /// Chance of the next block landing on each cell after the selected
+    /// move; empty when the move is illegal.
+    pub fn heatmap(&self) -> Option<Vec<Vec<f64>>> {
+        let analysis = self.selected();
+
+        if analysis.legal {
+            Some(analysis.board.spawn_probabilities())
+        } else {
+            None
+        }
+    }
+}
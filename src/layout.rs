@@ -0,0 +1,153 @@
+use crate::board::Board;
+use failure::{bail, format_err, Error};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// What a place of a board's rectangle is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cell {
+    /// A place blocks can move to.
+    Open,
+    /// A place blocks cannot move past, drawn as a wall.
+    Wall,
+    /// A place missing from the board, drawn as nothing.
+    Hole,
+}
+
+impl Cell {
+    pub fn symbol(self) -> char {
+        match self {
+            Cell::Open => '.',
+            Cell::Wall => 'X',
+            Cell::Hole => '_',
+        }
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<Cell> {
+        [Cell::Open, Cell::Wall, Cell::Hole]
+            .iter()
+            .copied()
+            .find(|cell| cell.symbol() == symbol)
+    }
+}
+
+/// The shape of a board: a rectangle of places, some of which are walls or
+/// holes. Written row by row, top first, with `.` for a place, `X` for a wall
+/// and `_` for a hole. Layout files have one row per line and may have `#`
+/// comments:
+///
+/// ```text
+/// # a donut
+/// ....
+/// .__.
+/// .__.
+/// ....
+/// ```
+///
+/// Replays write it on one line with `/` between rows, as `..../.__./.__./....`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub cells: Vec<Vec<Cell>>,
+}
+
+impl Layout {
+    pub fn width(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// An empty grid of the shape of the layout.
+    pub fn board(&self) -> Board {
+        let mut board = Board::new(self.width(), self.height());
+        board.mask = Some(Arc::new(self.clone()));
+
+        board
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Layout, Error> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| format_err!("cannot read {}: {}", path.display(), e))?;
+
+        text.parse().map_err(|e| format_err!("{}: {}", path.display(), e))
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows = self
+            .cells
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", rows.join("/"))
+    }
+}
+
+/// Reads rows separated by new lines or by `/`.
+impl FromStr for Layout {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Layout, Error> {
+        let mut cells = Vec::new();
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            for row in line.split('/') {
+                let row = row
+                    .chars()
+                    .map(|symbol| {
+                        Cell::from_symbol(symbol)
+                            .ok_or_else(|| format_err!("invalid place `{}`", symbol))
+                    })
+                    .collect::<Result<Vec<Cell>, Error>>()?;
+                cells.push(row);
+            }
+        }
+
+        let width = cells.first().map_or(0, Vec::len);
+        if width == 0 || cells.iter().any(|row| row.len() != width) {
+            bail!("a layout must have rows of the same, non-zero length");
+        }
+        if !cells.iter().flatten().any(|&cell| cell == Cell::Open) {
+            bail!("a layout must have places");
+        }
+
+        Ok(Layout { cells })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let layout: Layout = "# a corner\n...\n.X.\n.._\n".parse().unwrap();
+
+        assert_eq!(3, layout.width());
+        assert_eq!(Cell::Wall, layout.cells[1][1]);
+        assert_eq!(Cell::Hole, layout.cells[2][2]);
+        assert_eq!(".../.X./.._", layout.to_string());
+        assert_eq!(layout, layout.to_string().parse().unwrap());
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_layouts() {
+        assert!("".parse::<Layout>().is_err());
+        assert!("../...".parse::<Layout>().is_err());
+        assert!("XX/__".parse::<Layout>().is_err());
+        assert!(".o".parse::<Layout>().is_err());
+    }
+}
@@ -0,0 +1,26 @@
+pub mod analysis;
+pub mod bench;
+pub mod bitboard;
+pub mod block;
+pub mod board;
+pub mod cli;
+pub mod condition;
+pub mod daily;
+pub mod editor;
+pub mod game;
+pub mod ghost;
+pub mod heuristic;
+pub mod layout;
+pub mod merge;
+pub mod position;
+pub mod puzzle;
+pub mod replay;
+pub mod simulate;
+pub mod spawner;
+pub mod strategy;
+pub mod tablebase;
+pub mod tile;
+pub mod topology;
+pub mod tune;
+pub mod versus;
+pub mod zone;
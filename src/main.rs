@@ -1,22 +1,260 @@
-mod block;
-mod board;
 mod event;
-mod game;
+mod inspect;
+mod review;
 
-use event::{Event, Events};
-use game::Game;
-use std::io;
+use event::{Config, Event, Events};
+use inspect::Inspect;
+use review::Review;
+use failure::{bail, format_err};
+use std::env;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
 use termion::event::Key;
 use termion::input::MouseTerminal;
 use termion::raw::IntoRawMode;
 use termion::screen::AlternateScreen;
-use tui::backend::TermionBackend;
-use tui::layout::{Alignment, Constraint, Direction, Layout};
+use tui::backend::{Backend, TermionBackend};
+use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Style};
 use tui::widgets::{Block, Borders, Paragraph, Text, Widget};
-use tui::Terminal;
+use tui::{Frame, Terminal};
+use tui_2048::cli::Options;
+use tui_2048::daily::{self, Date, DayResult};
+use tui_2048::editor::Editor;
+use tui_2048::block::SpawnRule;
+use tui_2048::board::{Board, Move};
+use tui_2048::condition::{LoseCondition, WinCondition};
+use tui_2048::layout::{self, Cell};
+use tui_2048::merge::{self, Classic, MergeRule, Slide};
+use tui_2048::game::Game;
+use tui_2048::ghost::Ghost;
+use tui_2048::position::Position;
+use tui_2048::puzzle::{self, Outcome};
+use tui_2048::replay::Replay;
+use tui_2048::spawner::{self, Spawner};
+use tui_2048::strategy::{Expectimax, Settings};
+use tui_2048::tablebase::Tablebase;
+use tui_2048::tile::Tile;
+use tui_2048::topology::Topology;
+use tui_2048::versus::{self, Versus};
+use tui_2048::{analysis, bench, block, simulate, tablebase, tune};
+
+const OPTIONS: &[&str] = &[
+    "goal", "width", "height", "rotate-every", "board", "layout", "ghost", "rule", "topology",
+    "radius", "size", "specials", "spawner", "hot-seat", "versus", "target-score", "survival",
+    "time-limit", "move-limit", "zen", "tablebase", "pack", "puzzle", "daily", "date", "results",
+    "edit",
+];
 
 fn main() -> Result<(), failure::Error> {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+
+    match args.first().map(String::as_str) {
+        Some("simulate") => simulate::main(&args[1..]),
+        Some("bench") => bench::main(&args[1..]),
+        Some("tune") => tune::main(&args[1..]),
+        Some("analyze") => analysis::main(&args[1..]),
+        Some("tablebase") => tablebase::main(&args[1..]),
+        Some("puzzles") => puzzle::main(&args[1..]),
+        Some("daily") => daily::main(&args[1..]),
+        _ => play(&args),
+    }
+}
+
+fn play(args: &[String]) -> Result<(), failure::Error> {
+    let options = Options::parse(args, OPTIONS)?;
+    let goal: u64 = options.get("goal", 2048)?;
+    let mut width: usize = options.get("width", 4)?;
+    let mut height: usize = options.get("height", 4)?;
+    let rotate_every: u32 = options.get("rotate-every", 0)?;
+    let start = options
+        .string("board")
+        .map(str::parse::<Position>)
+        .transpose()?;
+
+    let layout = options
+        .string("layout")
+        .map(layout::Layout::load)
+        .transpose()?;
+    let mut ghost = options
+        .string("ghost")
+        .map(Replay::load)
+        .transpose()?
+        .map(|replay| Ghost::new(&replay));
+
+    if let Some(position) = &start {
+        width = position.board.width;
+        height = position.board.height;
+    }
+    if let Some(layout) = &layout {
+        width = layout.width();
+        height = layout.height();
+    }
+
+    let merge_rule = match options.string("rule") {
+        Some(name) => match merge::by_name(name) {
+            Some(rule) => rule,
+            None => bail!("unknown rule `{}`", name),
+        },
+        None => &Classic,
+    };
+    let classic = merge_rule.name() == Classic.name();
+    let topology = match options.string("topology") {
+        Some(name) => match Topology::from_name(name) {
+            Some(topology) => topology,
+            None => bail!("unknown topology `{}`", name),
+        },
+        None => ghost
+            .as_ref()
+            .map_or(Topology::Grid, |ghost| ghost.game.board.topology),
+    };
+    match topology {
+        Topology::Hex => {
+            let radius: usize = options.get("radius", 2)?;
+
+            width = 2 * radius + 1;
+            height = 2 * radius + 1;
+        }
+        Topology::Cube => {
+            let size: usize = options.get("size", 4)?;
+
+            width = size * size;
+            height = size;
+        }
+        Topology::Grid | Topology::Torus => {}
+    }
+    let specials = match options.string("specials") {
+        Some(chance) => chance
+            .parse()
+            .ok()
+            .filter(|chance| (0.0..=1.0).contains(chance))
+            .ok_or_else(|| format_err!("invalid value for --specials: {}", chance))?,
+        None if options.flag("specials") => 0.1,
+        None => 0.0,
+    };
+    let spawner: &'static dyn Spawner = match options.string("spawner") {
+        Some(name) => spawner::by_name(name).ok_or_else(|| format_err!("unknown spawner: {}", name))?,
+        None => &spawner::Random,
+    };
+    // the classic rule on a grid with random blocks, which everything else
+    // assumes
+    let standard = classic
+        && topology == Topology::Grid
+        && layout.is_none()
+        && specials == 0.0
+        && spawner.name() == spawner::Random.name()
+        && ghost.is_none();
+
+    if (classic && !goal.is_power_of_two()) || goal < 4 || width == 0 || height == 0 {
+        bail!("--goal must be a power of two of at least 4 and the board must not be empty");
+    }
+    if ghost.is_some()
+        && [
+            "goal", "width", "height", "board", "layout", "rule", "topology", "specials",
+            "spawner", "zen", "rotate-every", "edit", "tablebase", "puzzle", "daily", "date",
+            "hot-seat", "versus",
+        ]
+        .iter()
+        .any(|name| options.flag(name))
+    {
+        bail!("--ghost plays a game with the settings of its replay");
+    }
+    // board notation, puzzles and the engine all assume the classic rule on a
+    // grid
+    if !standard
+        && ["board", "edit", "tablebase", "puzzle", "daily", "date", "rotate-every"]
+            .iter()
+            .any(|name| options.flag(name))
+    {
+        bail!("--rule, --topology, --layout, --specials and --spawner only play plain games");
+    }
+    if layout.is_some() && topology != Topology::Grid {
+        bail!("--layout only shapes grids");
+    }
+    if topology.wraps() && merge_rule.slide() == Slide::OneStep {
+        bail!("blocks moving one step at a time cannot wrap around");
+    }
+    // special tiles slide fully and never wrap around
+    if specials > 0.0 && (topology.wraps() || merge_rule.slide() == Slide::OneStep) {
+        bail!("--specials cannot be played with threes or on a torus");
+    }
+    let hot_seat = options.flag("hot-seat");
+    // the second player picks places with hjkl, which also move on a cube
+    // and mean nothing on a hexagonal board
+    if hot_seat && !matches!(topology, Topology::Grid | Topology::Torus) {
+        bail!("--hot-seat only plays on a grid or a torus");
+    }
+    if hot_seat
+        && (merge_rule.slide() == Slide::OneStep
+            || specials > 0.0
+            || spawner.name() != spawner::Random.name())
+    {
+        bail!("--hot-seat cannot be played with threes, --specials or --spawner");
+    }
+    // puzzles and daily challenges keep their own rules
+    if hot_seat && ["puzzle", "daily", "date"].iter().any(|name| options.flag(name)) {
+        bail!("--hot-seat cannot be played with --puzzle, --daily or --date");
+    }
+    let versus = options.flag("versus");
+    // both players move with four keys
+    if versus && !matches!(topology, Topology::Grid | Topology::Torus) {
+        bail!("--versus only plays on a grid or a torus");
+    }
+    if versus
+        && ["hot-seat", "edit", "tablebase", "puzzle", "daily", "date"]
+            .iter()
+            .any(|name| options.flag(name))
+    {
+        bail!("--versus plays a race of two plain games");
+    }
+
+    let win_condition = match (options.get("target-score", 0)?, options.get("survival", 0)?) {
+        (0, 0) => WinCondition::Goal,
+        (score, 0) => WinCondition::Score(score),
+        (0, seconds) => WinCondition::Survive(Duration::from_secs(seconds)),
+        _ => bail!("--target-score and --survival cannot be combined"),
+    };
+    let mut lose_conditions = Vec::new();
+    match options.get("time-limit", 0)? {
+        0 => {}
+        seconds => lose_conditions.push(LoseCondition::TimeLimit(Duration::from_secs(seconds))),
+    }
+    match options.get("move-limit", 0)? {
+        0 => {}
+        moves => lose_conditions.push(LoseCondition::MoveLimit(moves)),
+    }
+    let zen = options.flag("zen");
+    // puzzles and daily challenges keep their own rules
+    let with_conditions = |mut game: Game| {
+        game.win_condition = win_condition;
+        game.lose_conditions = lose_conditions.clone();
+        game.zen = zen;
+        game.hot_seat = hot_seat;
+        game.merging(merge_rule)
+            .reaching(goal)
+            .with_specials(specials)
+            .spawning(spawner)
+    };
+
+    let tablebase = match options.string("tablebase") {
+        Some(path) => {
+            let tablebase = Tablebase::load(path)?;
+
+            let spawn_rule = start
+                .as_ref()
+                .map_or(SpawnRule::default(), |position| position.spawn_rule);
+
+            if !tablebase.covers(&Board::new(width, height), block::exponent(goal), spawn_rule) {
+                bail!("{} was solved for another board, goal or spawn rule", path);
+            }
+
+            Some(tablebase)
+        }
+        None => None,
+    };
+
     let logo = r"
   ___   ___  _  _   ___  
  |__ \ / _ \| || | / _ \ 
@@ -32,12 +270,109 @@ fn main() -> Result<(), failure::Error> {
     let mut terminal = Terminal::new(backend)?;
     terminal.hide_cursor()?;
 
-    let events = Events::new();
+    // q moves up-left on a hexagonal board
+    let (quit, events) = match topology {
+        Topology::Hex => {
+            let config = Config {
+                exit_key: Key::Ctrl('c'),
+                ..Config::default()
+            };
+
+            (config.exit_key, Events::with_config(config))
+        }
+        Topology::Grid | Topology::Cube | Topology::Torus => (Key::Char('q'), Events::new()),
+    };
+
+    let puzzles = match options.string("pack") {
+        Some(path) => puzzle::load_pack(path)?,
+        None => puzzle::bundled(),
+    };
+    let mut puzzle = match options.get("puzzle", 0)? {
+        0 => None,
+        number if number <= puzzles.len() => Some(number - 1),
+        _ => bail!("--puzzle must be between 1 and {}", puzzles.len()),
+    };
+
+    let mut challenge = match options.string("date") {
+        Some(date) => Some(date.parse::<Date>()?),
+        None if options.flag("daily") => Some(Date::today()),
+        None => None,
+    };
+    let results_path = match options.string("results") {
+        Some(path) => PathBuf::from(path),
+        None => daily::Results::default_path()?,
+    };
+    let mut share = None;
 
     // Game initialization
-    let mut game = Game::new(2048, 4, 4);
+    let game = match (puzzle, start) {
+        _ if ghost.is_some() => ghost.as_ref().unwrap().game.clone(),
+        _ if challenge.is_some() => challenge.unwrap().game(),
+        (Some(index), _) => puzzles[index].game(),
+        (None, Some(position)) => {
+            with_conditions(Game::from_position(goal, position, rand::random()))
+        }
+        (None, None) => {
+            let board = match &layout {
+                Some(layout) => layout.board(),
+                None => Board::with_topology(width, height, topology),
+            };
+
+            with_conditions(Game::with_board(goal, board, rand::random()))
+        }
+    };
+    // a race against a ghost keeps the rotation of its replay
+    let mut game = if ghost.is_some() {
+        game
+    } else {
+        game.rotating(rotate_every)
+    };
+
+    if versus {
+        return race(&mut terminal, &events, quit, Versus::new(game));
+    }
+
+    let mut review: Option<Review> = None;
+    let mut inspect: Option<Inspect> = None;
+    let mut engine = Expectimax::new(3, Settings::default().heuristic);
+    let mut notice = String::new();
+    let mut editor = if options.flag("edit") {
+        Some(Editor::new(game.board.clone()))
+    } else {
+        None
+    };
+    let mut last_tick = Instant::now();
+    // place picked by the second player of a hot-seat game
+    let mut place = (0, 0);
 
     loop {
+        let board = match (&editor, &review) {
+            (Some(editor), _) => &editor.board,
+            (None, Some(review)) => review.board(),
+            (None, None) => &game.board,
+        };
+        let cursor = match &editor {
+            Some(editor) => Some((editor.row, editor.col)),
+            None if review.is_none() && game.placing() => Some(place),
+            None => None,
+        };
+        let over = match puzzle {
+            Some(index) => puzzles[index].outcome(&game) != Outcome::Playing,
+            None => game.win() || game.lose(),
+        };
+        // puzzles and the daily challenge are played without the inspector
+        // and the editor
+        let free = standard && challenge.is_none() && puzzle.is_none();
+
+        if let (Some(date), true, None) = (challenge, over, &share) {
+            let result = DayResult::new(date, &game);
+            let mut results = daily::Results::load(&results_path)?;
+
+            share = Some(result.share());
+            results.record(result);
+            results.save(&results_path)?;
+        }
+
         terminal.draw(|mut f| {
             let chunks = Layout::default()
                 .direction(Direction::Horizontal)
@@ -93,101 +428,815 @@ fn main() -> Result<(), failure::Error> {
 
                     // status
                     {
-                        let message;
-                        let score = format!("score: {}", game.score);
+                        let message = match &review {
+                            _ if editor.is_some() => format!("editing {}x{}", board.width, board.height),
+                            Some(review) => review.status(),
+                            None if challenge.is_some() && !over => {
+                                format!("daily {}\nscore: {}", challenge.unwrap(), game.score)
+                            }
+                            None if puzzle.is_some() => {
+                                let current = &puzzles[puzzle.unwrap()];
+                                let moves = match current.move_limit {
+                                    Some(limit) => format!("{}/{}", game.moves, limit),
+                                    None => game.moves.to_string(),
+                                };
+                                let state = match current.outcome(&game) {
+                                    Outcome::Playing => format!("moves: {}", moves),
+                                    Outcome::Solved { stars } => {
+                                        format!("Solved! {}", "*".repeat(stars as usize))
+                                    }
+                                    Outcome::Failed(reason) => format!("Failed: {}", reason),
+                                };
+
+                                format!("{}\n{}\n{}", current.name, current.description(), state)
+                            }
+                            None if game.win() => match game.win_condition {
+                                WinCondition::Survive(_) => {
+                                    format!("Time's up!\nscore: {}", game.score)
+                                }
+                                _ if game.hot_seat => "The mover wins!".to_string(),
+                                _ => "You win!".to_string(),
+                            },
+                            None if game.lose() && game.hot_seat => "The spawner wins!".to_string(),
+                            None if game.lose() => "You lose!".to_string(),
+                            None => {
+                                let mut message = match game.win_condition {
+                                    WinCondition::Score(target) => {
+                                        format!("score: {}/{}", game.score, target)
+                                    }
+                                    _ => format!("score: {}", game.score),
+                                };
+                                if let Some(next) = game.next_block() {
+                                    let next = game.merge_rule.label(next);
+                                    message += &format!("\nnext: {}", next);
+                                }
+                                if game.zen {
+                                    message = format!("zen\n{}\ncleared: {}", message, game.clears);
+                                }
+                                if let Some(time) = game.time_left() {
+                                    let seconds = time.as_secs() + u64::from(time.subsec_nanos() > 0);
+                                    message += &format!("\ntime: {}:{:02}", seconds / 60, seconds % 60);
+                                }
+                                if let Some(moves) = game.moves_left() {
+                                    message += &format!("\nmoves left: {}", moves);
+                                }
+                                if game.placing() {
+                                    message += "\nspawner's turn";
+                                } else if game.hot_seat {
+                                    message += "\nmover's turn";
+                                }
 
-                        if game.win() {
-                            message = "You win!";
-                        } else if game.lose() {
-                            message = "You lose!";
+                                message
+                            }
+                        };
+                        let message = if editor.is_some() {
+                            message
+                        } else if inspect.is_some() {
+                            format!("{}\nhjkl: pick, esc: back", message)
+                        } else if review.is_some() {
+                            format!("{}\nn/p: step, x: export, esc: back\n{}", message, notice)
+                        } else if over && challenge.is_some() {
+                            format!("{}\nq: quit and print the share text\n{}", message, notice)
+                        } else if over && puzzle.is_some() {
+                            format!("{}\nr: retry, n: next puzzle\n{}", message, notice)
+                        } else if over && !standard {
+                            format!("{}\nw: save replay\n{}", message, notice)
+                        } else if over {
+                            format!("{}\na: analyze, w: save replay\n{}", message, notice)
+                        } else if game.placing() {
+                            format!("{}\nhjkl: pick a place, 2/4: put a block", message)
+                        } else if topology == Topology::Hex {
+                            format!("{}\nq w e a s d: move, ctrl-c: quit", message)
+                        } else if topology == Topology::Cube {
+                            format!("{}\nhjkl: move, i/o: in/out", message)
+                        } else if !free {
+                            message
                         } else {
-                            message = &score;
-                        }
+                            format!("{}\na: analyze, e: edit", message)
+                        };
 
                         let block = Block::default().title("status").borders(Borders::ALL);
 
                         Paragraph::new([Text::raw(message)].iter())
                             .block(block)
                             .alignment(Alignment::Center)
+                            .wrap(true)
                             .render(&mut f, chunks[1]);
                     }
                 }
 
-                Block::default().render(&mut f, chunks[1]);
+                if editor.is_some() {
+                    let message = format!(
+                        "hjkl: move, +/-: double/halve, x: clear, c: clear all, r: fill\n\
+                         HJKL: resize, e: export, enter: play from here, esc: back\n{}",
+                        notice
+                    );
+                    let block = Block::default().title("editor").borders(Borders::ALL);
 
-                {
-                    let constraints = (0..game.height)
-                        .map(|_i| Constraint::Percentage(100 / (game.height as u16)))
-                        .collect::<Vec<Constraint>>();
-                    let chunks = Layout::default()
-                        .direction(Direction::Vertical)
-                        .constraints(constraints.as_ref())
-                        .split(chunks[1]);
-                    for i in 0..game.height {
-                        Block::default().render(&mut f, chunks[i]);
-                        {
-                            let constraints = (0..game.width)
-                                .map(|_i| Constraint::Percentage(100 / (game.width as u16)))
-                                .collect::<Vec<Constraint>>();
-                            let chunks = Layout::default()
-                                .direction(Direction::Horizontal)
-                                .constraints(constraints.as_ref())
-                                .split(chunks[i]);
-                            let block = Block::default().borders(Borders::ALL);
-
-                            for j in 0..game.width {
-                                let value = game.board.blocks[i][j];
-
-                                let text;
-                                if value == 0 {
-                                    text = "".to_string();
-                                } else {
-                                    text = format!("{}", value);
-                                }
+                    Paragraph::new([Text::raw(message)].iter())
+                        .block(block)
+                        .alignment(Alignment::Center)
+                        .wrap(true)
+                        .render(&mut f, chunks[2]);
+                } else if let Some(review) = &review {
+                    let block = Block::default().title("analysis").borders(Borders::ALL);
 
-                                let r = 255;
-                                let g;
-                                let b = 0;
-                                if value == 0 {
-                                    g = 0;
-                                } else {
-                                    g = 128 + (128 / value) as u8;
-                                }
+                    Paragraph::new([Text::raw(review.details())].iter())
+                        .block(block)
+                        .alignment(Alignment::Center)
+                        .render(&mut f, chunks[2]);
+                } else if let Some(tablebase) = &tablebase {
+                    // perfect play
+                    let covered = tablebase.covers(
+                        &game.board,
+                        block::exponent(game.goal()),
+                        game.spawn_rule,
+                    );
+                    let message = match tablebase.win_probability(&game.board) {
+                        _ if !covered => "tablebase is for another board".to_string(),
+                        Some(probability) => {
+                            let best = match tablebase.best_move(&game.board) {
+                                Some((direction, _)) => direction.to_string(),
+                                None => "none".to_string(),
+                            };
 
-                                Paragraph::new(
-                                    [Text::styled(
-                                        text,
-                                        Style::default().fg(Color::Rgb(r, g, b)),
-                                    )]
-                                    .iter(),
-                                )
-                                .block(block.clone())
-                                .alignment(Alignment::Center)
-                                .render(&mut f, chunks[j]);
-                            }
+                            format!(
+                                "win with perfect play: {:.1}%\nbest move: {}",
+                                probability * 100.0,
+                                best
+                            )
                         }
+                        None => "position not in tablebase".to_string(),
+                    };
+                    let block = Block::default().title("tablebase").borders(Borders::ALL);
+
+                    Paragraph::new([Text::raw(message)].iter())
+                        .block(block)
+                        .alignment(Alignment::Center)
+                        .render(&mut f, chunks[2]);
+                }
+
+                // the layers of a cube need the whole width
+                let area = match board.topology {
+                    Topology::Cube => Rect::new(0, chunks[1].y, f.size().width, chunks[1].height),
+                    _ => chunks[1],
+                };
+
+                draw_board(&mut f, board, game.merge_rule, cursor, game.spawned, area);
+            }
+
+            // the ghost, racing the game
+            if let Some(ghost) = &ghost {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints(
+                        [
+                            Constraint::Percentage(25),
+                            Constraint::Percentage(60),
+                            Constraint::Percentage(15),
+                        ]
+                        .as_ref(),
+                    )
+                    .split(chunks[2]);
+                let header = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
+                    .split(chunks[0]);
+                let rule = ghost.game.merge_rule;
+                let progress = if ghost.finished() {
+                    "finished".to_string()
+                } else {
+                    format!("move {}/{}", ghost.moves(), ghost.length())
+                };
+                let message = format!(
+                    "{}\nscore: {} (you: {:+})\nbest: {} (you: {})",
+                    progress,
+                    ghost.game.score,
+                    ghost.lead(&game),
+                    rule.label(ghost.game.board.max_exponent()),
+                    rule.label(game.board.max_exponent()),
+                );
+                let block = Block::default().title("ghost").borders(Borders::ALL);
+
+                Paragraph::new([Text::raw(message)].iter())
+                    .block(block)
+                    .alignment(Alignment::Center)
+                    .wrap(true)
+                    .render(&mut f, header[1]);
+
+                draw_board(&mut f, &ghost.game.board, rule, None, ghost.game.spawned, chunks[1]);
+            }
+
+            // position analysis
+            if let Some(inspect) = &inspect {
+                let block = Block::default().title("moves").borders(Borders::ALL);
+
+                Paragraph::new([Text::raw(inspect.table())].iter())
+                    .block(block)
+                    .alignment(Alignment::Left)
+                    .render(&mut f, chunks[2]);
+
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Percentage(25), Constraint::Percentage(60)].as_ref())
+                    .split(chunks[0]);
+                let title = inspect.heatmap_title();
+                let mut block = Block::default().title(&title).borders(Borders::ALL);
+                block.render(&mut f, chunks[1]);
+
+                if let Some(heatmap) = inspect.heatmap() {
+                    let cells = heatmap
+                        .iter()
+                        .map(|row| {
+                            row.iter()
+                                .map(|&probability| {
+                                    let text = if probability > 0.0 {
+                                        format!("{:.0}%", probability * 100.0)
+                                    } else {
+                                        String::new()
+                                    };
+                                    let b = 64 + (191.0 * probability) as u8;
+
+                                    Text::styled(text, Style::default().fg(Color::Rgb(0, 128, b)))
+                                })
+                                .collect()
+                        })
+                        .collect::<Vec<_>>();
+
+                    draw_grid(&mut f, &cells, None, block.inner(chunks[1]));
+                }
+            }
+        })?;
+
+        let key = match events.next()? {
+            Event::Input(key) => key,
+            Event::Tick => {
+                let now = Instant::now();
+
+                // the clock stops while the board is edited, inspected or
+                // reviewed
+                if editor.is_none() && inspect.is_none() && review.is_none() {
+                    game.tick(now - last_tick);
+                }
+                last_tick = now;
+                continue;
+            }
+        };
+
+        if let Some(edited) = &mut editor {
+            match key {
+                key if key == quit => break,
+                Key::Char('h') => edited.move_cursor(Move::Left),
+                Key::Char('j') => edited.move_cursor(Move::Down),
+                Key::Char('k') => edited.move_cursor(Move::Up),
+                Key::Char('l') => edited.move_cursor(Move::Right),
+                Key::Char('H') => edited.resize(-1, 0),
+                Key::Char('J') => edited.resize(0, 1),
+                Key::Char('K') => edited.resize(0, -1),
+                Key::Char('L') => edited.resize(1, 0),
+                Key::Char('+') | Key::Char('=') => edited.raise(),
+                Key::Char('-') => edited.lower(),
+                Key::Char('x') => edited.clear(),
+                Key::Char('c') => edited.clear_all(),
+                Key::Char('r') => edited.fill_randomly(game.spawn_rule, &mut rand::thread_rng()),
+                Key::Char('e') => {
+                    let notation = edited.board.to_string();
+                    let files = [("txt", format!("{}\n", notation))];
+
+                    notice = match write_new("tui-2048-board", &files) {
+                        Ok(paths) => format!("saved {} to {}", notation, paths[0]),
+                        Err(e) => format!("cannot save the board: {}", e),
+                    };
+                }
+                Key::Char('\n') => match edited.validate() {
+                    Ok(board) => {
+                        let position = Position {
+                            board,
+                            score: 0,
+                            spawn_rule: game.spawn_rule,
+                        };
+
+                        game = with_conditions(Game::from_position(
+                            game.goal(),
+                            position,
+                            rand::random(),
+                        ))
+                        .rotating(rotate_every);
+                        editor = None;
+                        review = None;
+                        puzzle = None;
+                        challenge = None;
+                        notice.clear();
                     }
+                    Err(e) => notice = e.to_string(),
+                },
+                Key::Esc => {
+                    editor = None;
+                    notice.clear();
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if let Some(view) = &mut inspect {
+            match key {
+                key if key == quit => break,
+                Key::Char('h') => view.select(Move::Left),
+                Key::Char('j') => view.select(Move::Down),
+                Key::Char('k') => view.select(Move::Up),
+                Key::Char('l') => view.select(Move::Right),
+                Key::Char('a') | Key::Esc => inspect = None,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (key, &mut review) {
+            (key, _) if key == quit => break,
+            (Key::Esc, Some(_)) => review = None,
+            (Key::Char('l'), Some(review)) | (Key::Char('n'), Some(review)) => review.step(1),
+            (Key::Char('h'), Some(review)) | (Key::Char('p'), Some(review)) => review.step(-1),
+            (Key::Char('x'), Some(review)) => notice = review.export(game.seed),
+            (Key::Char('a'), None) if over && standard => {
+                match analysis::analyze(&game.replay(), &Settings::default()) {
+                    Ok(report) => review = Some(Review::new(report)),
+                    Err(e) => notice = e.to_string(),
                 }
             }
+            (Key::Char('w'), None) if over => {
+                let path = format!("tui-2048-{}.replay", game.seed);
+
+                game.replay().save(&path)?;
+                notice = format!("saved {}", path);
+            }
+            (Key::Char('r'), None) if over && puzzle.is_some() => {
+                game = puzzles[puzzle.unwrap()].game();
+                notice.clear();
+            }
+            (Key::Char('n'), None) if over && puzzle.is_some() => {
+                let next = (puzzle.unwrap() + 1) % puzzles.len();
+
+                puzzle = Some(next);
+                game = puzzles[next].game();
+                notice.clear();
+            }
+            (_, None) if !over && game.placing() => match (key, direction(topology, key)) {
+                (_, Some(direction)) => place = step(&game.board, place, direction),
+                (Key::Char('2'), _) => {
+                    game.place(place.0, place.1, 1);
+                }
+                (Key::Char('4'), _) => {
+                    game.place(place.0, place.1, 2);
+                }
+                _ => {}
+            },
+            (_, None) if !over => match (key, direction(topology, key)) {
+                (_, Some(direction))
+                    if puzzle.is_none_or(|index| puzzles[index].allows(direction)) =>
+                {
+                    let moved = game.play(direction);
+
+                    // the ghost plays along
+                    if let (true, Some(ghost)) = (moved, &mut ghost) {
+                        ghost.step();
+                    }
+                }
+                (Key::Char('a'), _) if free => {
+                    inspect = Some(Inspect::new(&game.board, game.spawn_rule, &mut engine))
+                }
+                (Key::Char('e'), _) if free => editor = Some(Editor::new(game.board.clone())),
+                _ => {}
+            },
+            (Key::Char('e'), None) if free => editor = Some(Editor::new(game.board.clone())),
+            _ => {}
+        }
+    }
+
+    if let Some(share) = share {
+        drop(terminal);
+        println!("{}", share);
+    }
+
+    Ok(())
+}
+
+/// Plays a race between two players sharing the keyboard on boards side by
+/// side, until `quit` is pressed.
+fn race<B: Backend>(
+    terminal: &mut Terminal<B>,
+    events: &Events,
+    quit: Key,
+    mut versus: Versus,
+) -> Result<(), failure::Error> {
+    let mut last_tick = Instant::now();
+
+    loop {
+        let outcome = versus.outcome();
+
+        terminal.draw(|mut f| {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(25), Constraint::Percentage(60)].as_ref())
+                .split(f.size());
+            let header = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Percentage(35),
+                        Constraint::Percentage(30),
+                        Constraint::Percentage(35),
+                    ]
+                    .as_ref(),
+                )
+                .split(chunks[0]);
+            let boards = Layout::default()
+                .direction(Direction::Horizontal)
+                .horizontal_margin(2)
+                .constraints(
+                    [
+                        Constraint::Percentage(47),
+                        Constraint::Percentage(6),
+                        Constraint::Percentage(47),
+                    ]
+                    .as_ref(),
+                )
+                .split(chunks[1]);
+
+            // what the race is about, then who won it
+            {
+                let game = &versus.games[0];
+                let message = match outcome {
+                    versus::Outcome::Playing => match game.time_left() {
+                        Some(_) => format!("race to {}\nor the highest score", game.goal()),
+                        None => format!("race to {}", game.goal()),
+                    },
+                    versus::Outcome::Won(player) => format!("Player {} wins!\nq: quit", player + 1),
+                    versus::Outcome::Draw => "Draw!\nq: quit".to_string(),
+                };
+                let block = Block::default().title("versus").borders(Borders::ALL);
+
+                Paragraph::new([Text::raw(message)].iter())
+                    .block(block)
+                    .alignment(Alignment::Center)
+                    .wrap(true)
+                    .render(&mut f, header[1]);
+            }
+
+            for (player, game) in versus.games.iter().enumerate() {
+                let best = game.merge_rule.label(game.board.max_exponent());
+                let mut message = format!("score: {}\nbest: {}", game.score, best);
+                if let Some(time) = game.time_left() {
+                    let seconds = time.as_secs() + u64::from(time.subsec_nanos() > 0);
+                    message += &format!("\ntime: {}:{:02}", seconds / 60, seconds % 60);
+                }
+                if outcome == versus::Outcome::Playing {
+                    message += if game.lose() {
+                        "\nno move left"
+                    } else if player == 0 {
+                        "\nw a s d: move"
+                    } else {
+                        "\narrows: move"
+                    };
+                }
+                let title = format!("player {}", player + 1);
+                let block = Block::default().title(&title).borders(Borders::ALL);
+
+                Paragraph::new([Text::raw(message)].iter())
+                    .block(block)
+                    .alignment(Alignment::Center)
+                    .wrap(true)
+                    .render(&mut f, header[player * 2]);
+
+                let area = boards[player * 2];
+
+                draw_board(&mut f, &game.board, game.merge_rule, None, game.spawned, area);
+            }
         })?;
 
         match events.next()? {
-            Event::Input(key) => match key {
-                Key::Char('q') => break,
-                _ => {
-                    if !game.win() && !game.lose() {
-                        match key {
-                            Key::Char('h') => game.move_left(),
-                            Key::Char('j') => game.move_down(),
-                            Key::Char('k') => game.move_up(),
-                            Key::Char('l') => game.move_right(),
-                            _ => {}
-                        }
-                    }
+            Event::Input(key) if key == quit => break,
+            Event::Input(key) => {
+                if let Some((player, direction)) = racer(key) {
+                    versus.play(player, direction);
                 }
             }
-        };
+            Event::Tick => {
+                let now = Instant::now();
+
+                versus.tick(now - last_tick);
+                last_tick = now;
+            }
+        }
     }
 
     Ok(())
 }
+
+/// The player moving with `key` in a race and their move: `w a s d` for the
+/// first player and the arrow keys for the second.
+fn racer(key: Key) -> Option<(usize, Move)> {
+    match key {
+        Key::Char('w') => Some((0, Move::Up)),
+        Key::Char('a') => Some((0, Move::Left)),
+        Key::Char('s') => Some((0, Move::Down)),
+        Key::Char('d') => Some((0, Move::Right)),
+        Key::Up => Some((1, Move::Up)),
+        Key::Left => Some((1, Move::Left)),
+        Key::Down => Some((1, Move::Down)),
+        Key::Right => Some((1, Move::Right)),
+        _ => None,
+    }
+}
+
+/// The move `key` makes on a board of `topology`: vim keys on a grid or a
+/// torus, `q w e a s d` on a hexagonal board and vim keys with `i` and `o` on a
+/// cube.
+fn direction(topology: Topology, key: Key) -> Option<Move> {
+    match (topology, key) {
+        (topology, Key::Char('h')) if topology != Topology::Hex => Some(Move::Left),
+        (topology, Key::Char('j')) if topology != Topology::Hex => Some(Move::Down),
+        (topology, Key::Char('k')) if topology != Topology::Hex => Some(Move::Up),
+        (topology, Key::Char('l')) if topology != Topology::Hex => Some(Move::Right),
+        (Topology::Cube, Key::Char('i')) => Some(Move::In),
+        (Topology::Cube, Key::Char('o')) => Some(Move::Out),
+        (Topology::Hex, Key::Char('q')) => Some(Move::UpLeft),
+        (Topology::Hex, Key::Char('w')) => Some(Move::Up),
+        (Topology::Hex, Key::Char('e')) => Some(Move::UpRight),
+        (Topology::Hex, Key::Char('a')) => Some(Move::DownLeft),
+        (Topology::Hex, Key::Char('s')) => Some(Move::Down),
+        (Topology::Hex, Key::Char('d')) => Some(Move::DownRight),
+        _ => None,
+    }
+}
+
+/// `place` moved one place in `direction`, staying on `board`.
+fn step(board: &Board, (row, col): (usize, usize), direction: Move) -> (usize, usize) {
+    match direction {
+        Move::Up => (row.saturating_sub(1), col),
+        Move::Down => ((row + 1).min(board.height - 1), col),
+        Move::Left => (row, col.saturating_sub(1)),
+        Move::Right => (row, (col + 1).min(board.width - 1)),
+        _ => (row, col),
+    }
+}
+
+/// Writes each of `files`, an extension and its contents, to `stem` with
+/// that extension, or to `stem-2`, `stem-3` and so on when one of them
+/// exists, so that nothing is overwritten. Returns the paths written.
+fn write_new(stem: &str, files: &[(&str, String)]) -> io::Result<Vec<String>> {
+    let mut number = 1;
+
+    loop {
+        let paths = files
+            .iter()
+            .map(|(extension, _)| match number {
+                1 => format!("{}.{}", stem, extension),
+                _ => format!("{}-{}.{}", stem, number, extension),
+            })
+            .collect::<Vec<_>>();
+
+        if paths.iter().all(|path| !Path::new(path).exists()) {
+            for (path, (_, contents)) in paths.iter().zip(files) {
+                OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(path)?
+                    .write_all(contents.as_bytes())?;
+            }
+
+            return Ok(paths);
+        }
+        number += 1;
+    }
+}
+
+/// Draws the blocks of `board`, worth what `rule` says, highlighting the
+/// block at `cursor`. On a cube the layer of the `newest` block is
+/// highlighted too.
+fn draw_board<B: Backend>(
+    f: &mut Frame<B>,
+    board: &Board,
+    rule: &dyn MergeRule,
+    cursor: Option<(usize, usize)>,
+    newest: Option<(usize, usize)>,
+    area: Rect,
+) {
+    let cells = board
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(j, &tile)| {
+                    let text = tile.label(rule);
+                    let exponent = tile.rank();
+
+                    let shade = if exponent == 0 {
+                        0
+                    } else {
+                        128 + 128u32.checked_shr(exponent as u32).unwrap_or(0) as u8
+                    };
+                    // special tiles in their own colors, then orange to red
+                    // for the classic rule, blue 1s and red 2s in Threes,
+                    // green to blue for the others
+                    let color = match (rule.name(), tile) {
+                        (_, Tile::Rock(_)) => Color::Gray,
+                        (_, Tile::Wildcard) => Color::Magenta,
+                        (_, Tile::Bomb(_)) => Color::Red,
+                        (_, Tile::Negative(_)) => Color::Cyan,
+                        (name, _) if name == Classic.name() => Color::Rgb(255, shade, 0),
+                        ("threes", Tile::Block(1)) => Color::Rgb(102, 204, 255),
+                        ("threes", Tile::Block(2)) => Color::Rgb(255, 102, 128),
+                        ("threes", _) => Color::White,
+                        _ => Color::Rgb(0, shade, 255),
+                    };
+                    let style = Style::default().fg(color);
+
+                    if cursor == Some((i, j)) {
+                        let text = if text.is_empty() { "_".to_string() } else { text };
+
+                        Text::styled(text, style.bg(Color::DarkGray))
+                    } else {
+                        Text::styled(text, style)
+                    }
+                })
+                .collect()
+        })
+        .collect::<Vec<_>>();
+
+    match board.topology {
+        Topology::Grid => draw_grid(f, &cells, board.mask.as_deref(), area),
+        Topology::Torus => draw_torus(f, &cells, area),
+        Topology::Hex => draw_hex(f, board, &cells, area),
+        Topology::Cube => {
+            let active = newest.map_or(0, |(_, col)| col / board.height);
+
+            draw_cube(f, board, &cells, active, area)
+        }
+    }
+}
+
+/// Draws the layers of a cube side by side, front first, with a border
+/// around each and the `active` one in yellow.
+fn draw_cube<B: Backend>(
+    f: &mut Frame<B>,
+    board: &Board,
+    cells: &[Vec<Text>],
+    active: usize,
+    area: Rect,
+) {
+    let size = board.height;
+    let constraints = (0..size)
+        .map(|_| Constraint::Percentage(100 / size as u16))
+        .collect::<Vec<Constraint>>();
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints::<&[Constraint]>(constraints.as_ref())
+        .split(area);
+
+    for (layer, chunk) in chunks.into_iter().enumerate() {
+        let title = format!("layer {}", layer + 1);
+        let style = if layer == active {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        let mut block = Block::default()
+            .title(&title)
+            .borders(Borders::ALL)
+            .border_style(style)
+            .title_style(style);
+        let layer_cells = cells
+            .iter()
+            .map(|row| row[layer * size..(layer + 1) * size].to_vec())
+            .collect::<Vec<_>>();
+
+        block.render(f, chunk);
+        draw_grid(f, &layer_cells, None, block.inner(chunk));
+    }
+}
+
+/// Draws the places of a hexagonal board column by column, each column half
+/// a place lower than the one on its left, see `Topology::Hex`.
+fn draw_hex<B: Backend>(f: &mut Frame<B>, board: &Board, cells: &[Vec<Text>], area: Rect) {
+    Block::default().render(f, area);
+
+    // the hexagon is as many places high as wide, plus half a place for the
+    // column offsets on each side
+    let radius = (board.width / 2) as u16;
+    let half = (area.height / (4 * radius + 2)).max(1);
+    let width = area.width / board.width as u16;
+    let block = Block::default().borders(Borders::ALL);
+
+    for (i, row) in cells.iter().enumerate() {
+        for (j, text) in row.iter().enumerate() {
+            if !board.contains(i, j) {
+                continue;
+            }
+
+            let y = (2 * i + j) as u16 - radius;
+            let chunk = Rect::new(area.x + j as u16 * width, area.y + y * half, width, 2 * half);
+
+            if chunk.bottom() <= area.bottom() {
+                Paragraph::new([text].iter().copied())
+                    .block(block)
+                    .alignment(Alignment::Center)
+                    .render(f, chunk);
+            }
+        }
+    }
+}
+
+/// Draws the board of a torus as a grid with arrows around it, in magenta,
+/// showing that every row and column wraps around.
+fn draw_torus<B: Backend>(f: &mut Frame<B>, cells: &[Vec<Text>], area: Rect) {
+    Block::default().render(f, area);
+
+    if area.width < 3 || area.height < 3 {
+        return draw_grid(f, cells, None, area);
+    }
+
+    let style = Style::default().fg(Color::Magenta);
+    let inner = Rect::new(area.x + 1, area.y + 1, area.width - 2, area.height - 2);
+    let split = |direction, count: usize, area| {
+        let constraints = (0..count)
+            .map(|_| Constraint::Percentage(100 / count as u16))
+            .collect::<Vec<Constraint>>();
+
+        Layout::default()
+            .direction(direction)
+            .constraints::<&[Constraint]>(constraints.as_ref())
+            .split(area)
+    };
+    let mut arrow = |text, chunk: Rect| {
+        Paragraph::new([Text::styled(text, style)].iter())
+            .alignment(Alignment::Center)
+            .render(f, chunk);
+    };
+
+    let columns = split(Direction::Horizontal, cells[0].len(), inner);
+    for chunk in columns {
+        arrow("↕", Rect::new(chunk.x, area.y, chunk.width, 1));
+        arrow("↕", Rect::new(chunk.x, area.bottom() - 1, chunk.width, 1));
+    }
+    let rows = split(Direction::Vertical, cells.len(), inner);
+    for chunk in rows {
+        let y = chunk.y + chunk.height / 2;
+
+        arrow("↔", Rect::new(area.x, y, 1, 1));
+        arrow("↔", Rect::new(area.right() - 1, y, 1, 1));
+    }
+
+    draw_grid(f, cells, None, inner);
+}
+
+/// Draws the places of a grid, the walls of `mask` filled in gray and its
+/// holes left out.
+fn draw_grid<B: Backend>(
+    f: &mut Frame<B>,
+    cells: &[Vec<Text>],
+    mask: Option<&layout::Layout>,
+    area: Rect,
+) {
+    Block::default().render(f, area);
+
+    let constraints = (0..cells.len())
+        .map(|_i| Constraint::Percentage(100 / (cells.len() as u16)))
+        .collect::<Vec<Constraint>>();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints::<&[Constraint]>(constraints.as_ref())
+        .split(area);
+    for (i, (row, chunk)) in cells.iter().zip(chunks).enumerate() {
+        Block::default().render(f, chunk);
+        {
+            let constraints = (0..row.len())
+                .map(|_i| Constraint::Percentage(100 / (row.len() as u16)))
+                .collect::<Vec<Constraint>>();
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints::<&[Constraint]>(constraints.as_ref())
+                .split(chunk);
+            let block = Block::default().borders(Borders::ALL);
+            let mut wall = block.style(Style::default().bg(Color::DarkGray));
+
+            for (j, (text, chunk)) in row.iter().zip(chunks).enumerate() {
+                match mask.map_or(Cell::Open, |mask| mask.cells[i][j]) {
+                    Cell::Open => {}
+                    Cell::Wall => {
+                        wall.render(f, chunk);
+                        continue;
+                    }
+                    Cell::Hole => continue,
+                }
+
+                Paragraph::new([text].iter().copied())
+                    .block(block)
+                    .alignment(Alignment::Center)
+                    .render(f, chunk);
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use crate::block::{self, MAX_EXPONENT};
+use std::fmt;
+
+/// How far blocks go on a move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Slide {
+    /// As far as they can, as in 2048.
+    Full,
+    /// One step, as in Threes. New blocks then enter from the opposite edge.
+    OneStep,
+}
+
+/// Which blocks merge and what they are worth. Blocks are stored as ranks,
+/// 0 being empty, and a rule gives every rank its value.
+pub trait MergeRule {
+    /// Name of the rule, as given to `--rule` and written in replays.
+    fn name(&self) -> &'static str;
+
+    /// Value of a block of rank `rank`.
+    fn value(&self, rank: u8) -> u64;
+
+    /// Text shown on a block of rank `rank`, empty for no block.
+    fn label(&self, rank: u8) -> String {
+        match rank {
+            0 => String::new(),
+            rank => self.value(rank).to_string(),
+        }
+    }
+
+    fn slide(&self) -> Slide {
+        Slide::Full
+    }
+
+    /// Ranks of the new blocks, dealt in a random order before the deck is
+    /// shuffled again. Empty when new blocks follow the spawn rule instead.
+    fn deck(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Rank of the block made by merging blocks of ranks `a` and `b`, if they
+    /// merge. Blocks never merge past `block::MAX_EXPONENT`.
+    fn merge(&self, a: u8, b: u8) -> Option<u8>;
+
+    /// Smallest rank worth at least `value`.
+    fn rank(&self, value: u64) -> u8 {
+        (1..u8::MAX)
+            .find(|&rank| self.value(rank) >= value)
+            .unwrap_or(u8::MAX)
+    }
+}
+
+impl fmt::Debug for dyn MergeRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl PartialEq for dyn MergeRule {
+    fn eq(&self, other: &dyn MergeRule) -> bool {
+        self.name() == other.name()
+    }
+}
+
+/// The rule of 2048: equal blocks merge and double.
+pub struct Classic;
+
+impl MergeRule for Classic {
+    fn name(&self) -> &'static str {
+        "classic"
+    }
+
+    fn value(&self, rank: u8) -> u64 {
+        block::value(rank)
+    }
+
+    fn label(&self, rank: u8) -> String {
+        block::label(rank)
+    }
+
+    fn merge(&self, a: u8, b: u8) -> Option<u8> {
+        next(a).filter(|_| a == b)
+    }
+
+    fn rank(&self, value: u64) -> u8 {
+        // values past 2^63 would overflow, so they get the largest block
+        match value.checked_next_power_of_two() {
+            Some(value) => block::exponent(value).max(1),
+            None => 63,
+        }
+    }
+}
+
+/// Blocks are Fibonacci numbers, from 1 and 2 up, and consecutive ones merge
+/// into their sum, as do two 1s.
+pub struct Fibonacci;
+
+impl MergeRule for Fibonacci {
+    fn name(&self) -> &'static str {
+        "fibonacci"
+    }
+
+    fn value(&self, rank: u8) -> u64 {
+        if rank == 0 {
+            return 0;
+        }
+
+        let (mut a, mut b) = (1u64, 2u64);
+        for _ in 1..rank {
+            let next = a.saturating_add(b);
+            a = b;
+            b = next;
+        }
+        a
+    }
+
+    fn merge(&self, a: u8, b: u8) -> Option<u8> {
+        match (a, b) {
+            (1, 1) => Some(2),
+            _ if a.max(b) - a.min(b) == 1 => next(a.max(b)),
+            _ => None,
+        }
+    }
+}
+
+/// The rules of Threes: 1s and 2s merge into 3s, equal blocks from 3 up merge
+/// and double, and blocks move one step at a time.
+pub struct Threes;
+
+impl MergeRule for Threes {
+    fn name(&self) -> &'static str {
+        "threes"
+    }
+
+    fn value(&self, rank: u8) -> u64 {
+        match rank {
+            0..=2 => u64::from(rank),
+            rank => 3u64.checked_shl(u32::from(rank) - 3).unwrap_or(0),
+        }
+    }
+
+    fn merge(&self, a: u8, b: u8) -> Option<u8> {
+        match (a, b) {
+            (1, 2) | (2, 1) => Some(3),
+            _ if a == b && a >= 3 => next(a),
+            _ => None,
+        }
+    }
+
+    fn slide(&self) -> Slide {
+        Slide::OneStep
+    }
+
+    fn deck(&self) -> Vec<u8> {
+        vec![1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3]
+    }
+}
+
+/// The rank after `rank`, unless it is past the largest block.
+fn next(rank: u8) -> Option<u8> {
+    rank.checked_add(1).filter(|&rank| rank <= MAX_EXPONENT)
+}
+
+pub const RULES: [&dyn MergeRule; 3] = [&Classic, &Fibonacci, &Threes];
+
+/// The rule called `name`.
+pub fn by_name(name: &str) -> Option<&'static dyn MergeRule> {
+    RULES.iter().copied().find(|rule| rule.name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classic() {
+        assert_eq!(Some(3), Classic.merge(2, 2));
+        assert_eq!(None, Classic.merge(2, 3));
+        assert_eq!(2048, Classic.value(11));
+        assert_eq!(11, Classic.rank(2048));
+        assert_eq!(Some(62), Classic.merge(61, 61));
+        assert_eq!(None, Classic.merge(62, 62));
+        assert_eq!(None, Classic.merge(255, 255));
+    }
+
+    #[test]
+    fn test_fibonacci() {
+        let values = (1..=8).map(|rank| Fibonacci.value(rank)).collect::<Vec<_>>();
+
+        assert_eq!(vec![1, 2, 3, 5, 8, 13, 21, 34], values);
+        assert_eq!(Some(2), Fibonacci.merge(1, 1));
+        assert_eq!(Some(3), Fibonacci.merge(1, 2));
+        assert_eq!(Some(5), Fibonacci.merge(4, 3));
+        assert_eq!(None, Fibonacci.merge(2, 2));
+        assert_eq!(None, Fibonacci.merge(2, 4));
+        // 2584 is the first Fibonacci number past 2048
+        assert_eq!(2584, Fibonacci.value(Fibonacci.rank(2048)));
+    }
+
+    #[test]
+    fn test_threes() {
+        let values = (1..=6).map(|rank| Threes.value(rank)).collect::<Vec<_>>();
+
+        assert_eq!(vec![1, 2, 3, 6, 12, 24], values);
+        assert_eq!(Some(3), Threes.merge(1, 2));
+        assert_eq!(Some(3), Threes.merge(2, 1));
+        assert_eq!(None, Threes.merge(1, 1));
+        assert_eq!(None, Threes.merge(2, 2));
+        assert_eq!(Some(4), Threes.merge(3, 3));
+        assert_eq!(None, Threes.merge(62, 62));
+        assert_eq!(3072, Threes.value(Threes.rank(2048)));
+    }
+
+    #[test]
+    fn test_by_name() {
+        assert_eq!("fibonacci", by_name("fibonacci").unwrap().name());
+        assert!(by_name("threes").is_some());
+        assert!(by_name("fives").is_none());
+    }
+}
@@ -0,0 +1,105 @@
+use crate::block::SpawnRule;
+use crate::board::Board;
+use failure::{bail, format_err, Error};
+use std::fmt;
+use std::str::FromStr;
+
+/// A board together with the game state around it. Written as the board
+/// notation followed by optional `score=N` and `four=P` (the chance of a new
+/// block being a 4), as in `2,.,./.,4,./.,.,. score=12 four=0.1`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub board: Board,
+    pub score: u64,
+    pub spawn_rule: SpawnRule,
+}
+
+impl From<Board> for Position {
+    fn from(board: Board) -> Position {
+        Position {
+            board,
+            score: 0,
+            spawn_rule: SpawnRule::default(),
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.board)?;
+        if self.score > 0 {
+            write!(f, " score={}", self.score)?;
+        }
+        if self.spawn_rule != SpawnRule::default() {
+            write!(f, " four={}", self.spawn_rule.four_probability)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Position {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Position, Error> {
+        let mut parts = text.split_whitespace();
+        let board = parts.next().unwrap_or("");
+        let mut position = Position::from(board.parse::<Board>()?);
+
+        for part in parts {
+            let mut pair = part.splitn(2, '=');
+            let name = pair.next().unwrap_or("");
+            let value = pair
+                .next()
+                .ok_or_else(|| format_err!("expected `name=value`, found `{}`", part))?;
+            let invalid = || format_err!("invalid {} `{}`", name, value);
+
+            match name {
+                "score" => position.score = value.parse().map_err(|_| invalid())?,
+                "four" => {
+                    let probability: f64 = value.parse().map_err(|_| invalid())?;
+                    if !(0.0..=1.0).contains(&probability) {
+                        return Err(invalid());
+                    }
+                    position.spawn_rule.four_probability = probability;
+                }
+                _ => bail!("unknown field `{}`", name),
+            }
+        }
+
+        Ok(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let position: Position = "2,.,./.,4,./.,.,2048 score=12 four=0.1".parse().unwrap();
+
+        assert_eq!(3, position.board.width);
+        assert_eq!(11, position.board.blocks[2][2].rank());
+        assert_eq!(12, position.score);
+        assert_eq!(0.1, position.spawn_rule.four_probability);
+        assert_eq!("2,.,./.,4,./.,.,2048 score=12 four=0.1", position.to_string());
+    }
+
+    #[test]
+    fn test_defaults() {
+        let position: Position = "2,./.,.".parse().unwrap();
+
+        assert_eq!(0, position.score);
+        assert_eq!(SpawnRule::default(), position.spawn_rule);
+        assert_eq!("2,./.,.", position.to_string());
+    }
+
+    #[test]
+    fn test_rejects_bad_metadata() {
+        assert!("2,./.,. score".parse::<Position>().is_err());
+        assert!("2,./.,. score=x".parse::<Position>().is_err());
+        assert!("2,./.,. four=2".parse::<Position>().is_err());
+        assert!("2,./.,. speed=1".parse::<Position>().is_err());
+    }
+}
@@ -0,0 +1,460 @@
+use crate::block;
+use crate::board::Move;
+use crate::cli::Options;
+use crate::condition::WinCondition;
+use crate::game::{Game, Spawn};
+use crate::position::Position;
+use failure::{bail, format_err, Error};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+pub const USAGE: &str = "usage: tui-2048 puzzles [--pack FILE]";
+
+const OPTIONS: &[&str] = &["pack", "help"];
+
+/// Puzzles which ship with the game.
+const BUNDLED: &str = include_str!("../puzzles/basics.pack");
+
+/// What a puzzle asks for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Objective {
+    /// Make a block with the given exponent.
+    Reach(u8),
+    /// Leave at most this many blocks on the board.
+    AtMostBlocks(usize),
+    /// Score at least this many points.
+    Score(u64),
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Objective::Reach(exponent) => write!(f, "reach {}", block::label(*exponent)),
+            Objective::AtMostBlocks(blocks) => write!(f, "blocks {}", blocks),
+            Objective::Score(score) => write!(f, "score {}", score),
+        }
+    }
+}
+
+impl Objective {
+    fn parse(text: &str) -> Option<Objective> {
+        let mut parts = text.split_whitespace();
+        let objective = match (parts.next()?, parts.next()?) {
+            ("reach", value) => Objective::Reach(block::parse(value)?),
+            ("blocks", blocks) => Objective::AtMostBlocks(blocks.parse().ok()?),
+            ("score", score) => Objective::Score(score.parse().ok()?),
+            _ => return None,
+        };
+
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(objective)
+    }
+
+    fn is_met(self, game: &Game) -> bool {
+        match self {
+            Objective::Reach(exponent) => game.board.max_exponent() >= exponent,
+            Objective::AtMostBlocks(blocks) => {
+                let board = &game.board;
+
+                board.width * board.height - board.empty_indexes().len() <= blocks
+            }
+            Objective::Score(score) => game.score >= score,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Playing,
+    Solved { stars: u8 },
+    Failed(&'static str),
+}
+
+/// A starting position with an objective and the rules to reach it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Puzzle {
+    pub name: String,
+    pub start: Position,
+    pub objective: Objective,
+    pub move_limit: Option<u32>,
+    /// Directions which may not be played.
+    pub forbidden: Vec<Move>,
+    /// New blocks put after the first moves, see `Game::with_spawns`.
+    pub spawns: Vec<Spawn>,
+    /// Most moves for three and for two stars; any solution earns one.
+    pub stars: Option<(u32, u32)>,
+    /// Seed of the random blocks once `spawns` runs out.
+    pub seed: u64,
+}
+
+impl Puzzle {
+    fn new(name: &str) -> Puzzle {
+        Puzzle {
+            name: name.to_string(),
+            start: Position::from(crate::board::Board::new(4, 4)),
+            objective: Objective::Reach(block::exponent(2048)),
+            move_limit: None,
+            forbidden: Vec::new(),
+            spawns: Vec::new(),
+            stars: None,
+            seed: 0,
+        }
+    }
+
+    /// The objective and limits in words, as in "make 512 in 10 moves".
+    pub fn description(&self) -> String {
+        let mut description = match self.objective {
+            Objective::Reach(exponent) => format!("make {}", block::label(exponent)),
+            Objective::AtMostBlocks(blocks) => format!("clear to at most {} blocks", blocks),
+            Objective::Score(score) => format!("score {} points", score),
+        };
+
+        if let Some(limit) = self.move_limit {
+            description += &format!(" in {} moves", limit);
+        }
+        if !self.forbidden.is_empty() {
+            let forbidden = self
+                .forbidden
+                .iter()
+                .map(Move::to_string)
+                .collect::<Vec<_>>();
+            description += &format!(" without moving {}", forbidden.join(" or "));
+        }
+
+        description
+    }
+
+    pub fn game(&self) -> Game {
+        // the objective decides the end of the game, not the goal
+        Game::from_position(2048, self.start.clone(), self.seed)
+            .with_spawns(self.spawns.clone())
+            .winning(WinCondition::Never)
+    }
+
+    pub fn allows(&self, direction: Move) -> bool {
+        !self.forbidden.contains(&direction)
+    }
+
+    pub fn stars(&self, moves: u32) -> u8 {
+        match self.stars {
+            Some((three, _)) if moves <= three => 3,
+            Some((_, two)) if moves <= two => 2,
+            Some(_) => 1,
+            None => 3,
+        }
+    }
+
+    pub fn outcome(&self, game: &Game) -> Outcome {
+        if self.objective.is_met(game) {
+            return Outcome::Solved {
+                stars: self.stars(game.moves),
+            };
+        }
+        if self.move_limit.is_some_and(|limit| game.moves >= limit) {
+            return Outcome::Failed("out of moves");
+        }
+
+        let stuck = Move::ALL.iter().all(|&direction| {
+            !self.allows(direction) || !game.board.try_to_move(direction).updated
+        });
+        if stuck {
+            return Outcome::Failed("no moves left");
+        }
+
+        Outcome::Playing
+    }
+}
+
+/// Puzzles are written as sections of `name = value` lines, each starting
+/// with the puzzle's name in brackets:
+///
+/// ```text
+/// [Corner squeeze]
+/// board = 256,128,.,./.,.,.,./.,.,.,./.,.,.,.
+/// objective = reach 512
+/// moves = 10
+/// forbid = U
+/// spawns = 2@0,3 4@3,3
+/// stars = 6 8
+/// seed = 1
+/// ```
+///
+/// `objective` is `reach VALUE`, `blocks N` or `score N`. Only `board` is
+/// required besides the name.
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "[{}]", self.name)?;
+        writeln!(f, "board = {}", self.start)?;
+        writeln!(f, "objective = {}", self.objective)?;
+        if let Some(limit) = self.move_limit {
+            writeln!(f, "moves = {}", limit)?;
+        }
+        if !self.forbidden.is_empty() {
+            let forbidden = self
+                .forbidden
+                .iter()
+                .map(|direction| direction.letter())
+                .collect::<String>();
+            writeln!(f, "forbid = {}", forbidden)?;
+        }
+        if !self.spawns.is_empty() {
+            let spawns = self.spawns.iter().map(Spawn::to_string).collect::<Vec<_>>();
+            writeln!(f, "spawns = {}", spawns.join(" "))?;
+        }
+        if let Some((three, two)) = self.stars {
+            writeln!(f, "stars = {} {}", three, two)?;
+        }
+        writeln!(f, "seed = {}", self.seed)
+    }
+}
+
+pub fn parse_pack(text: &str) -> Result<Vec<Puzzle>, Error> {
+    let mut puzzles: Vec<Puzzle> = Vec::new();
+    let mut has_board = true;
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            if !has_board {
+                bail!("line {}: the previous puzzle has no board", number + 1);
+            }
+            puzzles.push(Puzzle::new(line[1..line.len() - 1].trim()));
+            has_board = false;
+            continue;
+        }
+
+        let puzzle = puzzles
+            .last_mut()
+            .ok_or_else(|| format_err!("line {}: expected `[name]`", number + 1))?;
+        let mut parts = line.splitn(2, '=');
+        let name = parts.next().unwrap_or("").trim();
+        let value = parts
+            .next()
+            .map(str::trim)
+            .ok_or_else(|| format_err!("line {}: expected `name = value`", number + 1))?;
+        let invalid = || format_err!("line {}: invalid {} `{}`", number + 1, name, value);
+
+        match name {
+            "board" => {
+                puzzle.start = value
+                    .parse()
+                    .map_err(|e| format_err!("line {}: {}", number + 1, e))?;
+                has_board = true;
+            }
+            "objective" => puzzle.objective = Objective::parse(value).ok_or_else(invalid)?,
+            "moves" => puzzle.move_limit = Some(value.parse().map_err(|_| invalid())?),
+            "forbid" => {
+                puzzle.forbidden = value
+                    .chars()
+                    .map(Move::from_letter)
+                    .collect::<Option<Vec<Move>>>()
+                    .ok_or_else(invalid)?
+            }
+            "spawns" => {
+                puzzle.spawns = value
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<Vec<Spawn>, Error>>()
+                    .map_err(|e| format_err!("line {}: {}", number + 1, e))?
+            }
+            "stars" => {
+                let moves = value
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<Vec<u32>, _>>()
+                    .map_err(|_| invalid())?;
+                match moves[..] {
+                    [three, two] if three <= two => puzzle.stars = Some((three, two)),
+                    _ => return Err(invalid()),
+                }
+            }
+            "seed" => puzzle.seed = value.parse().map_err(|_| invalid())?,
+            _ => bail!("line {}: unknown setting `{}`", number + 1, name),
+        }
+    }
+
+    if !has_board {
+        bail!("the last puzzle has no board");
+    }
+
+    for puzzle in &puzzles {
+        let board = &puzzle.start.board;
+        let outside = puzzle
+            .spawns
+            .iter()
+            .any(|spawn| spawn.row >= board.height || spawn.col >= board.width);
+        if outside {
+            bail!("{}: a spawn is outside the board", puzzle.name);
+        }
+    }
+
+    Ok(puzzles)
+}
+
+pub fn load_pack<P: AsRef<Path>>(path: P) -> Result<Vec<Puzzle>, Error> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)
+        .map_err(|e| format_err!("cannot read {}: {}", path.display(), e))?;
+
+    parse_pack(&text).map_err(|e| format_err!("{}: {}", path.display(), e))
+}
+
+pub fn bundled() -> Vec<Puzzle> {
+    parse_pack(BUNDLED).unwrap()
+}
+
+/// Lists the puzzles of a pack, which also checks that it can be read.
+pub fn main(args: &[String]) -> Result<(), Error> {
+    let options = Options::parse(args, OPTIONS)?;
+
+    if options.flag("help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let puzzles = match options.string("pack") {
+        Some(path) => load_pack(path)?,
+        None => bundled(),
+    };
+
+    for (i, puzzle) in puzzles.iter().enumerate() {
+        println!("{:>3}. {}: {}", i + 1, puzzle.name, puzzle.description());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The fewest moves which solve `puzzle`, searching every allowed move.
+    fn solve(puzzle: &Puzzle, game: &Game) -> Option<u32> {
+        match puzzle.outcome(game) {
+            Outcome::Solved { .. } => return Some(game.moves),
+            Outcome::Failed(_) => return None,
+            Outcome::Playing => {}
+        }
+
+        Move::ALL
+            .iter()
+            .filter(|&&direction| puzzle.allows(direction))
+            .filter_map(|&direction| {
+                let mut next = game.clone();
+
+                if next.play(direction) {
+                    solve(puzzle, &next)
+                } else {
+                    None
+                }
+            })
+            .min()
+    }
+
+    #[test]
+    fn test_bundled_puzzles_can_be_solved() {
+        for puzzle in bundled() {
+            let moves = solve(&puzzle, &puzzle.game());
+
+            assert!(moves.is_some(), "{} cannot be solved", puzzle.name);
+            assert_eq!(
+                3,
+                puzzle.stars(moves.unwrap()),
+                "{} cannot earn three stars",
+                puzzle.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let puzzles = bundled();
+        let text = puzzles
+            .iter()
+            .map(Puzzle::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert_eq!(puzzles, parse_pack(&text).unwrap());
+    }
+
+    #[test]
+    fn test_parse_pack() {
+        let puzzles = parse_pack(
+            "# a pack\n\
+             [Up is lava]\n\
+             board = 2,2/.,.\n\
+             objective = blocks 1\n\
+             moves = 3\n\
+             forbid = UD\n\
+             spawns = 4@1,1\n\
+             stars = 1 2\n",
+        )
+        .unwrap();
+        let puzzle = &puzzles[0];
+
+        assert_eq!("Up is lava", puzzle.name);
+        assert_eq!(Objective::AtMostBlocks(1), puzzle.objective);
+        assert_eq!(Some(3), puzzle.move_limit);
+        assert_eq!(vec![Move::Up, Move::Down], puzzle.forbidden);
+        assert_eq!(Some((1, 2)), puzzle.stars);
+        assert_eq!(
+            "clear to at most 1 blocks in 3 moves without moving up or down",
+            puzzle.description()
+        );
+    }
+
+    #[test]
+    fn test_parse_pack_rejects_bad_packs() {
+        assert!(parse_pack("board = 2,.").is_err());
+        assert!(parse_pack("[a]\nobjective = reach 8").is_err());
+        assert!(parse_pack("[a]\nboard = 2,.\nobjective = reach 7").is_err());
+        assert!(parse_pack("[a]\nboard = 2,.\nstars = 3 2").is_err());
+        assert!(parse_pack("[a]\nboard = 2,.\nspawns = 2@1,0").is_err());
+        assert!(parse_pack("[a]\nboard = 2,.\nhint = left").is_err());
+    }
+
+    #[test]
+    fn test_outcome() {
+        let puzzles = parse_pack(
+            "[a]\n\
+             board = 2,2,4/.,.,.\n\
+             objective = reach 8\n\
+             moves = 2\n\
+             forbid = L\n\
+             spawns = 2@1,0 2@1,1\n\
+             stars = 1 1\n",
+        )
+        .unwrap();
+        let puzzle = &puzzles[0];
+
+        let mut game = puzzle.game();
+        assert_eq!(Outcome::Playing, puzzle.outcome(&game));
+        game.play(Move::Right);
+        assert_eq!(".,4,4/2,.,.", game.board.to_string());
+        assert_eq!(Outcome::Playing, puzzle.outcome(&game));
+        game.play(Move::Right);
+        assert_eq!(Outcome::Solved { stars: 1 }, puzzle.outcome(&game));
+
+        let mut game = puzzle.game();
+        game.play(Move::Down);
+        game.play(Move::Up);
+        assert_eq!(Outcome::Failed("out of moves"), puzzle.outcome(&game));
+    }
+
+    #[test]
+    fn test_game_is_not_won_by_a_goal() {
+        let puzzles = parse_pack("[a]\nboard = 2048,2/.,.\nobjective = reach 4096\n").unwrap();
+        let game = puzzles[0].game();
+
+        assert!(!game.win());
+        assert_eq!(Outcome::Playing, puzzles[0].outcome(&game));
+    }
+}
@@ -0,0 +1,499 @@
+use crate::board::{Board, Move};
+use crate::game::{Game, Spawn};
+use crate::layout::Layout;
+use crate::merge::{self, Classic, MergeRule, Slide};
+use crate::position::Position;
+use crate::spawner::{self, Spawner};
+use crate::topology::Topology;
+use failure::{bail, format_err, Error};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Everything needed to play a game again: its settings, the seed of its
+/// new blocks and its moves. Saved as `name = value` lines:
+///
+/// ```text
+/// seed = 42
+/// goal = 2048
+/// width = 4
+/// height = 4
+/// moves = ULLDR
+/// ```
+///
+/// Games which did not start from an empty board also have a `start` line
+/// with their first position in board notation, and games with fixed new
+/// blocks a `spawns` line listing them. Zen games have a `zen = true` line
+/// and games with another merge rule a `rule` line naming it, as do games on
+/// another board than a grid with a `topology` line. Games on a board with
+/// walls or holes keep its shape in a `layout` line, see `Layout`, and games
+/// with special tiles their chance in a `specials` line. Games whose new
+/// blocks are not random name their spawner in a `spawner` line, and games
+/// drawing their values apart from their places, see `Game::fair`, have a
+/// `fair = true` line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub goal: u64,
+    pub width: usize,
+    pub height: usize,
+    pub rotate_every: Option<u32>,
+    pub start: Option<Position>,
+    pub spawns: Vec<Spawn>,
+    pub zen: bool,
+    pub merge_rule: &'static dyn MergeRule,
+    pub topology: Topology,
+    pub layout: Option<Layout>,
+    pub specials: f64,
+    pub spawner: &'static dyn Spawner,
+    pub fair: bool,
+    pub moves: Vec<Move>,
+}
+
+impl Replay {
+    /// A new game with the settings of the replay.
+    pub fn game(&self) -> Game {
+        let game = match &self.start {
+            Some(position) => Game::from_position(self.goal, position.clone(), self.seed),
+            None => {
+                let board = match &self.layout {
+                    Some(layout) => layout.board(),
+                    None => Board::with_topology(self.width, self.height, self.topology),
+                };
+
+                Game::with_board(self.goal, board, self.seed)
+            }
+        };
+
+        let mut game = game
+            .with_spawns(self.spawns.clone())
+            .merging(self.merge_rule)
+            .reaching(self.goal)
+            .with_specials(self.specials)
+            .spawning(self.spawner);
+        game.zen = self.zen;
+        if self.fair {
+            game = game.fair();
+        }
+
+        match self.rotate_every {
+            Some(every) => game.rotating(every),
+            None => game,
+        }
+    }
+
+    /// The board before every move, followed by the final board.
+    pub fn positions(&self) -> Result<Vec<Board>, Error> {
+        let mut game = self.game();
+        let mut positions = vec![game.board.clone()];
+
+        for (i, &direction) in self.moves.iter().enumerate() {
+            if !game.play(direction) {
+                bail!("move {} ({}) does not change the board", i + 1, direction);
+            }
+            positions.push(game.board.clone());
+        }
+
+        Ok(positions)
+    }
+
+    pub fn parse(text: &str) -> Result<Replay, Error> {
+        let mut replay = Replay {
+            seed: 0,
+            goal: 2048,
+            width: 4,
+            height: 4,
+            rotate_every: None,
+            start: None,
+            spawns: Vec::new(),
+            zen: false,
+            merge_rule: &Classic,
+            topology: Topology::Grid,
+            layout: None,
+            specials: 0.0,
+            spawner: &spawner::Random,
+            fair: false,
+            moves: Vec::new(),
+        };
+        let mut has_seed = false;
+        // spawns are checked against the board once its size is known
+        let mut spawns_line = 0;
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let name = parts.next().unwrap_or("").trim();
+            let value = parts
+                .next()
+                .map(str::trim)
+                .ok_or_else(|| format_err!("line {}: expected `name = value`", number + 1))?;
+            let invalid = || format_err!("line {}: invalid {} `{}`", number + 1, name, value);
+
+            match name {
+                "seed" => {
+                    replay.seed = value.parse().map_err(|_| invalid())?;
+                    has_seed = true;
+                }
+                "goal" => replay.goal = value.parse().map_err(|_| invalid())?,
+                "width" => replay.width = value.parse().map_err(|_| invalid())?,
+                "height" => replay.height = value.parse().map_err(|_| invalid())?,
+                "rotate_every" => replay.rotate_every = Some(value.parse().map_err(|_| invalid())?),
+                "start" => {
+                    let position: Position = value
+                        .parse()
+                        .map_err(|e| format_err!("line {}: {}", number + 1, e))?;
+                    replay.width = position.board.width;
+                    replay.height = position.board.height;
+                    replay.start = Some(position);
+                }
+                "spawns" => {
+                    spawns_line = number + 1;
+                    replay.spawns = value
+                        .split_whitespace()
+                        .map(str::parse)
+                        .collect::<Result<Vec<Spawn>, Error>>()
+                        .map_err(|e| format_err!("line {}: {}", number + 1, e))?
+                }
+                "rule" => replay.merge_rule = merge::by_name(value).ok_or_else(invalid)?,
+                "topology" => replay.topology = Topology::from_name(value).ok_or_else(invalid)?,
+                "layout" => {
+                    let layout: Layout = value
+                        .parse()
+                        .map_err(|e| format_err!("line {}: {}", number + 1, e))?;
+                    replay.width = layout.width();
+                    replay.height = layout.height();
+                    replay.layout = Some(layout);
+                }
+                "specials" => {
+                    replay.specials = value
+                        .parse()
+                        .ok()
+                        .filter(|chance| (0.0..=1.0).contains(chance))
+                        .ok_or_else(invalid)?
+                }
+                "spawner" => replay.spawner = spawner::by_name(value).ok_or_else(invalid)?,
+                "fair" => replay.fair = value.parse().map_err(|_| invalid())?,
+                "zen" => replay.zen = value.parse().map_err(|_| invalid())?,
+                "moves" => {
+                    replay.moves = value
+                        .chars()
+                        .map(Move::from_letter)
+                        .collect::<Option<Vec<Move>>>()
+                        .ok_or_else(invalid)?
+                }
+                _ => bail!("line {}: unknown setting `{}`", number + 1, name),
+            }
+        }
+
+        if !has_seed {
+            bail!("the replay has no seed");
+        }
+        let rule = replay.merge_rule;
+        if replay.width == 0 || replay.height == 0 || rule.value(rule.rank(replay.goal)) != replay.goal {
+            bail!("the replay has an invalid board size or goal");
+        }
+        // layouts only shape grids
+        if replay.layout.is_some() && replay.topology != Topology::Grid {
+            bail!("a {} board cannot have a layout", replay.topology.name());
+        }
+        // a cube lays its layers side by side, and a hexagon fits a square
+        let shaped = match replay.topology {
+            Topology::Cube => replay.width == replay.height * replay.height,
+            Topology::Hex => replay.width == replay.height,
+            Topology::Grid | Topology::Torus => true,
+        };
+        if !shaped {
+            bail!("a {} board cannot be {}x{}", replay.topology.name(), replay.width, replay.height);
+        }
+        if replay
+            .spawns
+            .iter()
+            .any(|spawn| spawn.row >= replay.height || spawn.col >= replay.width)
+        {
+            bail!("line {}: a spawn is outside the board", spawns_line);
+        }
+        // only grids and tori can be turned
+        if replay.rotate_every.is_some() && !matches!(replay.topology, Topology::Grid | Topology::Torus) {
+            bail!("a {} board cannot be rotated", replay.topology.name());
+        }
+        // special tiles slide fully and never wrap around
+        if replay.specials > 0.0 && (replay.topology.wraps() || rule.slide() == Slide::OneStep) {
+            bail!("special tiles cannot be played with threes or on a torus");
+        }
+
+        Ok(replay)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Replay, Error> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| format_err!("cannot read {}: {}", path.display(), e))?;
+
+        Replay::parse(&text).map_err(|e| format_err!("{}: {}", path.display(), e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+
+        fs::write(path, self.to_string())
+            .map_err(|e| format_err!("cannot write {}: {}", path.display(), e))
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "seed = {}", self.seed)?;
+        writeln!(f, "goal = {}", self.goal)?;
+        writeln!(f, "width = {}", self.width)?;
+        writeln!(f, "height = {}", self.height)?;
+        if let Some(every) = self.rotate_every {
+            writeln!(f, "rotate_every = {}", every)?;
+        }
+        if let Some(position) = &self.start {
+            writeln!(f, "start = {}", position)?;
+        }
+        if !self.spawns.is_empty() {
+            let spawns = self
+                .spawns
+                .iter()
+                .map(Spawn::to_string)
+                .collect::<Vec<_>>();
+            writeln!(f, "spawns = {}", spawns.join(" "))?;
+        }
+        if self.zen {
+            writeln!(f, "zen = true")?;
+        }
+        if self.merge_rule.name() != Classic.name() {
+            writeln!(f, "rule = {}", self.merge_rule.name())?;
+        }
+        if self.topology != Topology::Grid {
+            writeln!(f, "topology = {}", self.topology.name())?;
+        }
+        if let Some(layout) = &self.layout {
+            writeln!(f, "layout = {}", layout)?;
+        }
+        if self.specials > 0.0 {
+            writeln!(f, "specials = {}", self.specials)?;
+        }
+        if self.spawner.name() != spawner::Random.name() {
+            writeln!(f, "spawner = {}", self.spawner.name())?;
+        }
+        if self.fair {
+            writeln!(f, "fair = true")?;
+        }
+
+        let moves = self
+            .moves
+            .iter()
+            .map(|direction| direction.letter())
+            .collect::<String>();
+        writeln!(f, "moves = {}", moves)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{Random, Strategy};
+
+    fn played(seed: u64) -> Game {
+        let mut game = Game::with_seed(2048, 4, 4, seed);
+        let mut strategy = Random::new(seed);
+
+        while let Some(direction) = strategy.next_move(&game.board) {
+            game.play(direction);
+        }
+
+        game
+    }
+
+    #[test]
+    fn test_positions_replay_the_game() {
+        let game = played(9);
+        let positions = game.replay().positions().unwrap();
+
+        assert_eq!(game.history.len() + 1, positions.len());
+        assert_eq!(&game.board, positions.last().unwrap());
+    }
+
+    #[test]
+    fn test_positions_reject_moves_which_do_nothing() {
+        // the game is over, so no further move changes the board
+        let mut replay = played(9).replay();
+        replay.moves.push(Move::Up);
+
+        assert!(replay.positions().is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut replay = played(3).replay();
+        replay.rotate_every = Some(5);
+        replay.zen = true;
+
+        assert_eq!(replay, Replay::parse(&replay.to_string()).unwrap());
+    }
+
+    #[test]
+    fn test_merge_rule() {
+        let mut game = Game::with_seed(2048, 4, 4, 2).merging(&merge::Fibonacci);
+        for _ in 0..30 {
+            Move::ALL.iter().any(|&direction| game.play(direction));
+        }
+
+        let replay = Replay::parse(&game.replay().to_string()).unwrap();
+
+        assert_eq!(2584, replay.goal);
+        assert_eq!(game.replay(), replay);
+        assert_eq!(&game.board, replay.positions().unwrap().last().unwrap());
+    }
+
+    #[test]
+    fn test_topology() {
+        let board = Board::with_topology(5, 5, Topology::Hex);
+        let mut game = Game::with_board(2048, board, 4);
+        for _ in 0..30 {
+            Move::HEX.iter().any(|&direction| game.play(direction));
+        }
+
+        let replay = Replay::parse(&game.replay().to_string()).unwrap();
+
+        assert_eq!(Topology::Hex, replay.topology);
+        assert_eq!(game.replay(), replay);
+        assert_eq!(&game.board, replay.positions().unwrap().last().unwrap());
+    }
+
+    #[test]
+    fn test_layout() {
+        let layout: Layout = ".X../..../..../.._.".parse().unwrap();
+        let mut game = Game::with_board(2048, layout.board(), 6);
+        for _ in 0..30 {
+            Move::ALL.iter().any(|&direction| game.play(direction));
+        }
+
+        let replay = Replay::parse(&game.replay().to_string()).unwrap();
+
+        assert_eq!(Some(layout), replay.layout);
+        assert_eq!(game.replay(), replay);
+        assert_eq!(&game.board, replay.positions().unwrap().last().unwrap());
+        assert!(game.board.blocks[0][1].is_empty());
+    }
+
+    #[test]
+    fn test_specials() {
+        let mut game = Game::with_seed(2048, 4, 4, 7).with_specials(0.5);
+        for _ in 0..40 {
+            Move::ALL.iter().any(|&direction| game.play(direction));
+        }
+
+        let replay = Replay::parse(&game.replay().to_string()).unwrap();
+
+        assert_eq!(0.5, replay.specials);
+        assert_eq!(game.replay(), replay);
+        assert_eq!(&game.board, replay.positions().unwrap().last().unwrap());
+    }
+
+    #[test]
+    fn test_spawner() {
+        let mut game = Game::with_seed(2048, 3, 3, 8).spawning(&spawner::Evil);
+        while Move::ALL.iter().any(|&direction| game.play(direction)) {}
+
+        let replay = Replay::parse(&game.replay().to_string()).unwrap();
+
+        assert_eq!("evil", replay.spawner.name());
+        assert_eq!(game.replay(), replay);
+        assert_eq!(&game.board, replay.positions().unwrap().last().unwrap());
+    }
+
+    #[test]
+    fn test_fair() {
+        let mut game = Game::with_seed(2048, 3, 3, 4).fair();
+        while Move::ALL.iter().any(|&direction| game.play(direction)) {}
+
+        let replay = Replay::parse(&game.replay().to_string()).unwrap();
+
+        assert!(replay.fair);
+        assert_eq!(&game.board, replay.positions().unwrap().last().unwrap());
+    }
+
+    #[test]
+    fn test_start_position() {
+        let position: Position = "2,2,.,./.,.,.,./.,.,.,./.,.,.,4 score=100 four=0".parse().unwrap();
+        let spawns = vec!["4@3,0".parse().unwrap()];
+        let mut game = Game::from_position(2048, position, 5).with_spawns(spawns);
+        game.play(Move::Left);
+
+        let replay = Replay::parse(&game.replay().to_string()).unwrap();
+        let positions = replay.positions().unwrap();
+
+        assert_eq!(replay, game.replay());
+        assert_eq!("2,2,.,./.,.,.,./.,.,.,./.,.,.,4", positions[0].to_string());
+        assert_eq!(game.board, positions[1]);
+        assert_eq!(2, positions[1].blocks[3][0].rank());
+        assert_eq!(100, replay.game().score);
+        assert_eq!(104, game.score);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_replays() {
+        assert!(Replay::parse("goal = 2048").is_err());
+        assert!(Replay::parse("seed = 1\nmoves = UX").is_err());
+        assert!(Replay::parse("seed = 1\nspeed = 2").is_err());
+        assert!(Replay::parse("seed = 1\nstart = 2,3").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_layouts_of_non_grids() {
+        let error = Replay::parse("seed = 1\ntopology = torus\nlayout = ..../.X../..../....").unwrap_err();
+
+        assert_eq!("a torus board cannot have a layout", error.to_string());
+        assert!(Replay::parse("seed = 1\ntopology = grid\nlayout = ..../.X../..../....").is_ok());
+    }
+
+    #[test]
+    fn test_parse_rejects_cubes_of_the_wrong_shape() {
+        let error = Replay::parse("seed = 1\ntopology = cube\nwidth = 4\nheight = 4").unwrap_err();
+
+        assert_eq!("a cube board cannot be 4x4", error.to_string());
+        assert!(Replay::parse("seed = 1\ntopology = cube\nwidth = 16\nheight = 4").is_ok());
+    }
+
+    #[test]
+    fn test_parse_rejects_hexagons_of_the_wrong_shape() {
+        let error = Replay::parse("seed = 1\ntopology = hex\nwidth = 5\nheight = 3").unwrap_err();
+
+        assert_eq!("a hex board cannot be 5x3", error.to_string());
+        assert!(Replay::parse("seed = 1\ntopology = hex\nwidth = 5\nheight = 5").is_ok());
+    }
+
+    #[test]
+    fn test_parse_rejects_spawns_outside_the_board() {
+        let error = Replay::parse("seed = 1\nspawns = 2@9,9\nmoves = L").unwrap_err();
+
+        assert_eq!("line 2: a spawn is outside the board", error.to_string());
+        assert!(Replay::parse("seed = 1\nspawns = 2@0,2\nwidth = 2").is_err());
+        assert!(Replay::parse("seed = 1\nspawns = 2@3,3").is_ok());
+    }
+
+    #[test]
+    fn test_parse_rejects_rotating_non_grids() {
+        let error = Replay::parse("seed = 1\ntopology = hex\nrotate_every = 3").unwrap_err();
+
+        assert_eq!("a hex board cannot be rotated", error.to_string());
+        assert!(Replay::parse("seed = 1\ntopology = torus\nrotate_every = 3").is_ok());
+    }
+
+    #[test]
+    fn test_parse_rejects_specials_with_threes_or_on_a_torus() {
+        let error = Replay::parse("seed = 1\ntopology = torus\nspecials = 0.1").unwrap_err();
+
+        assert_eq!("special tiles cannot be played with threes or on a torus", error.to_string());
+        assert!(Replay::parse("seed = 1\ngoal = 3072\nrule = threes\nspecials = 0.1").is_err());
+        assert!(Replay::parse("seed = 1\ngoal = 3072\nrule = threes").is_ok());
+        assert!(Replay::parse("seed = 1\ntopology = hex\nspecials = 0.1").is_ok());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::write_new;
+use tui_2048::analysis::Report;
+use tui_2048::board::Board;
+
+/// Steps through an analyzed game, one position at a time.
+pub struct Review {
+    report: Report,
+    /// Number of moves played before the shown position.
+    index: usize,
+}
+
+impl Review {
+    pub fn new(report: Report) -> Review {
+        Review { report, index: 0 }
+    }
+
+    pub fn step(&mut self, delta: isize) {
+        let index = self.index as isize + delta;
+
+        self.index = index.max(0).min(self.report.moves.len() as isize) as usize;
+    }
+
+    pub fn board(&self) -> &Board {
+        match self.report.moves.get(self.index) {
+            Some(analysis) => &analysis.board,
+            None => &self.report.final_board,
+        }
+    }
+
+    pub fn status(&self) -> String {
+        match self.report.moves.get(self.index) {
+            Some(analysis) => format!(
+                "move {}/{}: {} ({})",
+                self.index + 1,
+                self.report.moves.len(),
+                analysis.played,
+                analysis.verdict
+            ),
+            None => "final position".to_string(),
+        }
+    }
+
+    pub fn details(&self) -> String {
+        match self.report.moves.get(self.index) {
+            Some(analysis) => {
+                let values = analysis
+                    .values
+                    .iter()
+                    .map(|(direction, value)| format!("{}: {:.0}", direction, value))
+                    .collect::<Vec<_>>();
+
+                format!(
+                    "best move: {}, loss: {:.0}\n{}",
+                    analysis.best,
+                    analysis.loss,
+                    values.join("  ")
+                )
+            }
+            None => self
+                .report
+                .to_text()
+                .lines()
+                .last()
+                .unwrap_or("")
+                .to_string(),
+        }
+    }
+
+    /// Writes the report as text and JSON to new files in the working
+    /// directory and returns a message to show.
+    pub fn export(&self, seed: u64) -> String {
+        let files = [
+            ("txt", self.report.to_text() + "\n"),
+            ("json", self.report.to_json() + "\n"),
+        ];
+
+        match write_new(&format!("tui-2048-{}", seed), &files) {
+            Ok(paths) => format!("saved {} and {}", paths[0], paths[1]),
+            Err(e) => format!("cannot export: {}", e),
+        }
+    }
+}
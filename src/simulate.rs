@@ -0,0 +1,370 @@
+use crate::block;
+use crate::cli::Options;
+use crate::game::Game;
+use crate::heuristic::Weights;
+use crate::spawner::{self, Spawner};
+use crate::strategy::{self, Settings};
+use failure::{bail, format_err, Error};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const USAGE: &str = "usage: tui-2048 simulate [--strategy random|greedy|expectimax] [--games N] \
+[--threads N] [--seed N] [--goal N] [--width N] [--height N] [--depth N] [--weights FILE] \
+[--format text|csv|json] [--spawner random|corner|evil]";
+
+const OPTIONS: &[&str] = &[
+    "strategy", "games", "threads", "seed", "goal", "width", "height", "depth", "weights",
+    "format", "spawner", "help",
+];
+
+const PERCENTILES: [usize; 6] = [10, 25, 50, 75, 90, 99];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(Clone)]
+pub struct Config {
+    pub strategy: String,
+    pub games: usize,
+    pub threads: usize,
+    pub seed: u64,
+    pub goal: u64,
+    pub width: usize,
+    pub height: usize,
+    pub settings: Settings,
+    pub format: Format,
+    /// Decides where new blocks appear, to test strategies against harder
+    /// spawners than the random one.
+    pub spawner: &'static dyn Spawner,
+}
+
+impl Config {
+    pub fn from_options(options: &Options) -> Result<Config, Error> {
+        let format = match options.string("format").unwrap_or("text") {
+            "text" => Format::Text,
+            "csv" => Format::Csv,
+            "json" => Format::Json,
+            format => bail!("unknown format: {}", format),
+        };
+        let weights = match options.string("weights") {
+            Some(path) => Weights::load(path)?,
+            None => Weights::default(),
+        };
+        let config = Config {
+            strategy: options.string("strategy").unwrap_or("expectimax").to_string(),
+            games: options.get("games", 100)?,
+            threads: options.get("threads", 1)?,
+            seed: options.get("seed", 0)?,
+            goal: options.get("goal", 2048)?,
+            width: options.get("width", 4)?,
+            height: options.get("height", 4)?,
+            settings: Settings::new(options.get("depth", 3)?, weights),
+            format,
+            spawner: match options.string("spawner") {
+                Some(name) => spawner::by_name(name)
+                    .ok_or_else(|| format_err!("unknown spawner: {}", name))?,
+                None => &spawner::Random,
+            },
+        };
+
+        if config.threads == 0 || config.width == 0 || config.height == 0 {
+            bail!("--threads, --width and --height must be positive");
+        }
+        if !config.goal.is_power_of_two() || config.goal < 4 {
+            bail!("--goal must be a power of two of at least 4");
+        }
+        if config.strategy == "expectimax" && (config.width != 4 || config.height != 4) {
+            bail!("expectimax only plays 4x4 boards");
+        }
+
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameResult {
+    pub score: u64,
+    pub max_block: u64,
+    pub moves: u32,
+    pub won: bool,
+}
+
+pub struct Report {
+    pub results: Vec<GameResult>,
+    pub elapsed: Duration,
+}
+
+/// Plays one game to the end. Game `index` always uses the same seeds, so
+/// results do not depend on how games are spread over threads.
+pub fn play_one(config: &Config, index: usize) -> Result<GameResult, Error> {
+    let seed = config.seed.wrapping_add(index as u64);
+    let mut game = Game::with_seed(config.goal, config.width, config.height, seed).spawning(config.spawner);
+    let mut strategy = strategy::from_name(&config.strategy, seed, &config.settings)?;
+
+    while let Some(direction) = strategy.next_move(&game.board) {
+        game.play(direction);
+    }
+
+    let max_block = block::value(game.board.max_exponent());
+
+    Ok(GameResult {
+        score: game.score,
+        max_block,
+        moves: game.moves,
+        won: max_block >= config.goal,
+    })
+}
+
+pub fn run(config: &Config) -> Result<Report, Error> {
+    // fail early on an unknown strategy instead of once per thread
+    strategy::from_name(&config.strategy, config.seed, &config.settings)?;
+
+    let start = Instant::now();
+    let next = Arc::new(AtomicUsize::new(0));
+    let results = Arc::new(Mutex::new(vec![None; config.games]));
+
+    let handles = (0..config.threads.min(config.games.max(1)))
+        .map(|_| {
+            let config = config.clone();
+            let next = Arc::clone(&next);
+            let results = Arc::clone(&results);
+
+            thread::spawn(move || -> Result<(), Error> {
+                loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    if index >= config.games {
+                        return Ok(());
+                    }
+
+                    let result = play_one(&config, index)?;
+                    results.lock().unwrap()[index] = Some(result);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        match handle.join() {
+            Ok(result) => result?,
+            Err(_) => bail!("a simulation thread panicked"),
+        }
+    }
+
+    let results = results.lock().unwrap().iter().flatten().copied().collect();
+
+    Ok(Report {
+        results,
+        elapsed: start.elapsed(),
+    })
+}
+
+impl Report {
+    pub fn win_rate(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+
+        let wins = self.results.iter().filter(|result| result.won).count();
+
+        wins as f64 / self.results.len() as f64
+    }
+
+    pub fn mean_score(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+
+        let total: u64 = self.results.iter().map(|result| result.score).sum();
+
+        total as f64 / self.results.len() as f64
+    }
+
+    /// Nearest-rank percentile of the final scores.
+    pub fn score_percentile(&self, percentile: usize) -> u64 {
+        let mut scores = self.results.iter().map(|result| result.score).collect::<Vec<_>>();
+        scores.sort_unstable();
+
+        if scores.is_empty() {
+            return 0;
+        }
+
+        let rank = (percentile * scores.len()).div_ceil(100).max(1);
+
+        scores[rank - 1]
+    }
+
+    pub fn max_block_histogram(&self) -> BTreeMap<u64, usize> {
+        let mut histogram = BTreeMap::new();
+
+        for result in &self.results {
+            *histogram.entry(result.max_block).or_insert(0) += 1;
+        }
+
+        histogram
+    }
+
+    pub fn moves_per_sec(&self) -> f64 {
+        let moves: u64 = self.results.iter().map(|result| result.moves as u64).sum();
+        let seconds = self.elapsed.as_secs_f64();
+
+        if seconds > 0.0 {
+            moves as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    pub fn format(&self, format: Format) -> String {
+        match format {
+            Format::Text => self.to_text(),
+            Format::Csv => self.to_csv(),
+            Format::Json => self.to_json(),
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let mut lines = vec![
+            format!("games: {}", self.results.len()),
+            format!("win rate: {:.2}%", self.win_rate() * 100.0),
+        ];
+
+        for &percentile in &PERCENTILES {
+            lines.push(format!("score p{}: {}", percentile, self.score_percentile(percentile)));
+        }
+        lines.push("max block:".to_string());
+        for (block, count) in self.max_block_histogram() {
+            lines.push(format!("  {:>6}: {}", block, count));
+        }
+        lines.push(format!("moves/sec: {:.0}", self.moves_per_sec()));
+
+        lines.join("\n")
+    }
+
+    fn to_csv(&self) -> String {
+        let mut lines = vec![
+            "metric,value".to_string(),
+            format!("games,{}", self.results.len()),
+            format!("win_rate,{}", self.win_rate()),
+        ];
+
+        for &percentile in &PERCENTILES {
+            lines.push(format!("score_p{},{}", percentile, self.score_percentile(percentile)));
+        }
+        for (block, count) in self.max_block_histogram() {
+            lines.push(format!("max_block_{},{}", block, count));
+        }
+        lines.push(format!("moves_per_sec,{}", self.moves_per_sec()));
+
+        lines.join("\n")
+    }
+
+    fn to_json(&self) -> String {
+        let percentiles = PERCENTILES
+            .iter()
+            .map(|&percentile| format!("\"p{}\":{}", percentile, self.score_percentile(percentile)))
+            .collect::<Vec<_>>();
+        let histogram = self
+            .max_block_histogram()
+            .iter()
+            .map(|(block, count)| format!("\"{}\":{}", block, count))
+            .collect::<Vec<_>>();
+
+        format!(
+            "{{\"games\":{},\"win_rate\":{},\"score_percentiles\":{{{}}},\"max_block_histogram\":{{{}}},\"moves_per_sec\":{}}}",
+            self.results.len(),
+            self.win_rate(),
+            percentiles.join(","),
+            histogram.join(","),
+            self.moves_per_sec()
+        )
+    }
+}
+
+pub fn main(args: &[String]) -> Result<(), Error> {
+    let options = Options::parse(args, OPTIONS)?;
+
+    if options.flag("help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let config = Config::from_options(&options)?;
+    let report = run(&config)?;
+
+    println!("{}", report.format(config.format));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(threads: usize) -> Config {
+        Config {
+            strategy: "greedy".to_string(),
+            games: 6,
+            threads,
+            seed: 7,
+            goal: 64,
+            width: 3,
+            height: 3,
+            settings: Settings::default(),
+            format: Format::Text,
+            spawner: &spawner::Random,
+        }
+    }
+
+    fn report(scores: &[u64]) -> Report {
+        Report {
+            results: scores
+                .iter()
+                .map(|&score| GameResult {
+                    score,
+                    max_block: if score >= 100 { 64 } else { 32 },
+                    moves: 10,
+                    won: score >= 100,
+                })
+                .collect(),
+            elapsed: Duration::from_secs(2),
+        }
+    }
+
+    #[test]
+    fn test_run_is_reproducible_across_thread_counts() {
+        let single = run(&config(1)).unwrap();
+        let multi = run(&config(3)).unwrap();
+
+        assert_eq!(6, single.results.len());
+        assert_eq!(single.results, multi.results);
+    }
+
+    #[test]
+    fn test_report_statistics() {
+        let report = report(&[10, 20, 30, 100, 200]);
+
+        assert_eq!(0.4, report.win_rate());
+        assert_eq!(72.0, report.mean_score());
+        assert_eq!(10, report.score_percentile(10));
+        assert_eq!(30, report.score_percentile(50));
+        assert_eq!(200, report.score_percentile(99));
+        assert_eq!(Some(&3), report.max_block_histogram().get(&32));
+        assert_eq!(Some(&2), report.max_block_histogram().get(&64));
+        assert_eq!(25.0, report.moves_per_sec());
+    }
+
+    #[test]
+    fn test_report_json() {
+        let json = report(&[100]).format(Format::Json);
+
+        assert!(json.starts_with("{\"games\":1,\"win_rate\":1,"));
+        assert!(json.contains("\"max_block_histogram\":{\"64\":1}"));
+    }
+}
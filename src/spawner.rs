@@ -0,0 +1,211 @@
+use crate::block::SpawnRule;
+use crate::board::Board;
+use crate::merge::MergeRule;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::fmt;
+
+/// Decides where the block put after each move appears and what it is.
+pub trait Spawner: Sync {
+    /// Name of the spawner, as given to `--spawner` and written in replays.
+    fn name(&self) -> &'static str;
+
+    /// Place and exponent of the next block on `board`, whose blocks merge
+    /// by `rule`, or `None` when the board has no empty place. Values are
+    /// drawn by `spawn_rule` unless the spawner picks them itself.
+    fn spawn(
+        &self,
+        board: &Board,
+        rule: &dyn MergeRule,
+        spawn_rule: SpawnRule,
+        rng: &mut StdRng,
+    ) -> Option<(usize, usize, u8)>;
+}
+
+impl fmt::Debug for dyn Spawner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl PartialEq for dyn Spawner {
+    fn eq(&self, other: &dyn Spawner) -> bool {
+        self.name() == other.name()
+    }
+}
+
+/// Puts a block drawn by the spawn rule on an empty place picked uniformly at
+/// random, as in 2048.
+pub struct Random;
+
+impl Spawner for Random {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn spawn(
+        &self,
+        board: &Board,
+        _rule: &dyn MergeRule,
+        spawn_rule: SpawnRule,
+        rng: &mut StdRng,
+    ) -> Option<(usize, usize, u8)> {
+        let (row, col) = board.pick_empty_index(rng)?;
+
+        Some((row, col, spawn_rule.pick(rng)))
+    }
+}
+
+/// Puts a block drawn by the spawn rule on the first empty place, row by row
+/// from the top-left corner.
+pub struct Corner;
+
+impl Spawner for Corner {
+    fn name(&self) -> &'static str {
+        "corner"
+    }
+
+    fn spawn(
+        &self,
+        board: &Board,
+        _rule: &dyn MergeRule,
+        spawn_rule: SpawnRule,
+        rng: &mut StdRng,
+    ) -> Option<(usize, usize, u8)> {
+        let (row, col) = board.empty_indexes().first().copied()?;
+
+        Some((row, col, spawn_rule.pick(rng)))
+    }
+}
+
+/// Puts the worst block for the player: it tries every empty place and every
+/// value the spawn rule can draw, and searches `Evil::DEPTH` moves ahead for
+/// the one leaving the player the fewest empty places after their best
+/// replies. Ties are broken at random.
+pub struct Evil;
+
+impl Evil {
+    pub const DEPTH: u32 = 2;
+
+    /// Candidate blocks for `board`: every empty place with every value
+    /// `spawn_rule` can draw.
+    fn candidates(board: &Board, spawn_rule: SpawnRule) -> Vec<(usize, usize, u8)> {
+        let mut candidates = Vec::new();
+
+        for (row, col) in board.empty_indexes() {
+            for &(block, probability) in spawn_rule.choices().iter() {
+                if probability > 0.0 {
+                    candidates.push((row, col, block));
+                }
+            }
+        }
+
+        candidates
+    }
+
+    /// Value of `board` for the player about to move, searching `depth`
+    /// moves ahead: the most empty places their best move leaves, or a
+    /// negative value when no move is left, the lower the sooner.
+    fn player(board: &Board, rule: &dyn MergeRule, spawn_rule: SpawnRule, depth: u32) -> i64 {
+        let mut best = -1 - i64::from(depth);
+
+        for &direction in board.topology.directions() {
+            let next = board.try_to_move_with(direction, rule);
+            if !next.updated {
+                continue;
+            }
+
+            let value = if depth <= 1 {
+                next.empty_indexes().len() as i64
+            } else {
+                Evil::candidates(&next, spawn_rule)
+                    .into_iter()
+                    .map(|(row, col, block)| {
+                        let next = next.clone().put_new_block(row, col, block);
+
+                        Evil::player(&next, rule, spawn_rule, depth - 1)
+                    })
+                    .min()
+                    .unwrap_or(0)
+            };
+            best = best.max(value);
+        }
+
+        best
+    }
+}
+
+impl Spawner for Evil {
+    fn name(&self) -> &'static str {
+        "evil"
+    }
+
+    fn spawn(
+        &self,
+        board: &Board,
+        rule: &dyn MergeRule,
+        spawn_rule: SpawnRule,
+        rng: &mut StdRng,
+    ) -> Option<(usize, usize, u8)> {
+        let values = Evil::candidates(board, spawn_rule)
+            .into_iter()
+            .map(|(row, col, block)| {
+                let next = board.clone().put_new_block(row, col, block);
+
+                ((row, col, block), Evil::player(&next, rule, spawn_rule, Evil::DEPTH))
+            })
+            .collect::<Vec<_>>();
+        let worst = values.iter().map(|&(_, value)| value).min()?;
+        let choices = values
+            .into_iter()
+            .filter(|&(_, value)| value == worst)
+            .map(|(spawn, _)| spawn)
+            .collect::<Vec<_>>();
+
+        choices.choose(rng).copied()
+    }
+}
+
+pub const SPAWNERS: [&dyn Spawner; 3] = [&Random, &Corner, &Evil];
+
+/// The spawner called `name`.
+pub fn by_name(name: &str) -> Option<&'static dyn Spawner> {
+    SPAWNERS.iter().copied().find(|spawner| spawner.name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::Classic;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_corner() {
+        let board: Board = "2,4,./.,.,./.,.,.".parse().unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        let (row, col, _) = Corner.spawn(&board, &Classic, SpawnRule::default(), &mut rng).unwrap();
+
+        assert_eq!((0, 2), (row, col));
+    }
+
+    #[test]
+    fn test_evil() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        // a 2 leaves no move while a 4 would merge
+        let board: Board = "2,4/4,.".parse().unwrap();
+        assert_eq!(Some((1, 1, 1)), Evil.spawn(&board, &Classic, SpawnRule::default(), &mut rng));
+
+        // with only 4s to put, the one cell left gets a 4 all the same
+        let spawn_rule = SpawnRule { four_probability: 1.0 };
+        assert_eq!(Some((1, 1, 2)), Evil.spawn(&board, &Classic, spawn_rule, &mut rng));
+
+        assert_eq!(None, Evil.spawn(&"2,4".parse().unwrap(), &Classic, SpawnRule::default(), &mut rng));
+    }
+
+    #[test]
+    fn test_by_name() {
+        assert_eq!("evil", by_name("evil").unwrap().name());
+        assert!(by_name("nice").is_none());
+    }
+}
@@ -0,0 +1,276 @@
+use crate::bitboard::BitBoard;
+use crate::block::SpawnRule;
+use crate::board::{Board, Move};
+use crate::heuristic::{Heuristic, Weights};
+use failure::{bail, Error};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+pub const NAMES: [&str; 3] = ["random", "greedy", "expectimax"];
+
+/// Expectimax value of a board where no move is left. It is below anything a
+/// heuristic gives a board still in play, yet finite, so that averaging over
+/// new blocks still ranks a likely loss under an unlikely one.
+const LOST: f64 = -1e15;
+
+/// Picks the next move for a board, or `None` when no move changes it.
+pub trait Strategy {
+    fn next_move(&mut self, board: &Board) -> Option<Move>;
+}
+
+/// Options shared by the strategies which search ahead.
+#[derive(Clone)]
+pub struct Settings {
+    pub depth: u32,
+    pub heuristic: Arc<Heuristic>,
+}
+
+impl Settings {
+    pub fn new(depth: u32, weights: Weights) -> Settings {
+        Settings {
+            depth,
+            heuristic: Arc::new(Heuristic::new(weights)),
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings::new(3, Weights::default())
+    }
+}
+
+pub fn from_name(name: &str, seed: u64, settings: &Settings) -> Result<Box<dyn Strategy + Send>, Error> {
+    match name {
+        "random" => Ok(Box::new(Random::new(seed))),
+        "greedy" => Ok(Box::new(Greedy)),
+        "expectimax" => Ok(Box::new(Expectimax::new(
+            settings.depth,
+            Arc::clone(&settings.heuristic),
+        ))),
+        _ => bail!("unknown strategy: {} (expected one of {})", name, NAMES.join(", ")),
+    }
+}
+
+fn legal_moves(board: &Board) -> Vec<(Move, Board)> {
+    Move::ALL
+        .iter()
+        .map(|&direction| (direction, board.try_to_move(direction)))
+        .filter(|(_, next)| next.updated)
+        .collect()
+}
+
+/// Plays a uniformly random legal move.
+pub struct Random {
+    rng: StdRng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Strategy for Random {
+    fn next_move(&mut self, board: &Board) -> Option<Move> {
+        legal_moves(board).choose(&mut self.rng).map(|(direction, _)| *direction)
+    }
+}
+
+/// Plays the move with the best immediate score, preferring more empty
+/// blocks on ties.
+pub struct Greedy;
+
+impl Strategy for Greedy {
+    fn next_move(&mut self, board: &Board) -> Option<Move> {
+        legal_moves(board)
+            .into_iter()
+            .max_by_key(|(_, next)| (next.score, next.empty_indexes().len()))
+            .map(|(direction, _)| direction)
+    }
+}
+
+/// Searches `depth` moves ahead on 4x4 boards, averaging over every
+/// possible new block and scoring the leaves with a weighted heuristic.
+/// Values are cached per canonical board, so symmetric positions are only
+/// searched once per move.
+pub struct Expectimax {
+    depth: u32,
+    heuristic: Arc<Heuristic>,
+    cache: HashMap<BitBoard, (u32, f64)>,
+}
+
+impl Expectimax {
+    pub fn new(depth: u32, heuristic: Arc<Heuristic>) -> Expectimax {
+        Expectimax {
+            depth: depth.max(1),
+            heuristic,
+            cache: HashMap::new(),
+        }
+    }
+
+    fn max_node(&mut self, board: BitBoard, depth: u32) -> f64 {
+        let mut best = f64::NEG_INFINITY;
+
+        for &direction in Move::ALL.iter() {
+            let (next, _) = board.try_to_move(direction);
+
+            if next != board {
+                best = f64::max(best, self.chance_node(next, depth));
+            }
+        }
+
+        if best == f64::NEG_INFINITY {
+            LOST
+        } else {
+            best
+        }
+    }
+
+    fn chance_node(&mut self, board: BitBoard, depth: u32) -> f64 {
+        if depth == 0 {
+            return self.heuristic.evaluate(board);
+        }
+
+        let key = board.canonical();
+        if let Some(&(cached_depth, value)) = self.cache.get(&key) {
+            if cached_depth >= depth {
+                return value;
+            }
+        }
+
+        let empty = board.empty_indexes();
+        let mut total = 0.0;
+        for &(row, col) in &empty {
+            for &(exponent, probability) in &SpawnRule::default().choices() {
+                total += probability * self.max_node(board.set(row, col, exponent), depth - 1);
+            }
+        }
+        let value = total / empty.len() as f64;
+
+        self.cache.insert(key, (depth, value));
+
+        value
+    }
+
+    /// Expected value of every move which changes the board, in the order
+    /// of `Move::ALL`. Empty for boards which are not 4x4.
+    pub fn move_values(&mut self, board: &Board) -> Vec<(Move, f64)> {
+        let board = match BitBoard::try_from(board) {
+            Ok(board) => board,
+            Err(_) => return Vec::new(),
+        };
+
+        self.cache.clear();
+
+        Move::ALL
+            .iter()
+            .filter_map(|&direction| {
+                let (next, _) = board.try_to_move(direction);
+
+                if next == board {
+                    None
+                } else {
+                    Some((direction, self.chance_node(next, self.depth - 1)))
+                }
+            })
+            .collect()
+    }
+}
+
+impl Strategy for Expectimax {
+    /// Returns `None` for boards which are not 4x4.
+    fn next_move(&mut self, board: &Board) -> Option<Move> {
+        self.move_values(board)
+            .into_iter()
+            .fold(None, |best: Option<(Move, f64)>, (direction, value)| match best {
+                Some((_, best_value)) if best_value >= value => best,
+                _ => Some((direction, value)),
+            })
+            .map(|(direction, _)| direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(notation: &str) -> Board {
+        notation.parse().unwrap()
+    }
+
+    #[test]
+    fn test_strategies_return_none_when_stuck() {
+        let stuck = board("2,4/4,2");
+
+        for name in NAMES.iter() {
+            let mut strategy = from_name(name, 0, &Settings::default()).unwrap();
+
+            assert_eq!(None, strategy.next_move(&stuck));
+        }
+    }
+
+    #[test]
+    fn test_greedy_takes_biggest_merge() {
+        let blocks = board("2,.,.,8/2,.,.,8/.,.,.,./.,.,.,.");
+
+        let direction = Greedy.next_move(&blocks).unwrap();
+
+        assert!(direction == Move::Up || direction == Move::Down);
+    }
+
+    #[test]
+    fn test_expectimax_only_plays_legal_moves() {
+        let blocks = board("2,4,8,16/.,.,.,./.,.,.,./.,.,.,.");
+
+        let direction = Expectimax::new(2, Arc::new(Heuristic::new(Weights::default())))
+            .next_move(&blocks)
+            .unwrap();
+
+        assert_eq!(Move::Down, direction);
+    }
+
+    #[test]
+    fn test_expectimax_skips_boards_it_cannot_pack() {
+        let blocks = board("2,.,./.,.,./.,.,.");
+
+        assert_eq!(None, from_name("expectimax", 0, &Settings::default()).unwrap().next_move(&blocks));
+    }
+
+    #[test]
+    fn test_expectimax_ranks_lost_boards_below_negative_ones() {
+        // smoothness alone outweighs the bonus of every board in play
+        let weights = Weights {
+            empty: 0.0,
+            merges: 0.0,
+            monotonicity: 0.0,
+            smoothness: 1e6,
+        };
+        let heuristic = Arc::new(Heuristic::new(weights));
+        let mut engine = Expectimax::new(1, Arc::clone(&heuristic));
+        let blocks = BitBoard::try_from(&board("2,8,.,./.,.,.,./.,.,.,./.,.,.,.")).unwrap();
+        let children = Move::ALL
+            .iter()
+            .map(|&direction| blocks.try_to_move(direction).0)
+            .filter(|&next| next != blocks)
+            .map(|next| heuristic.evaluate(next))
+            .collect::<Vec<_>>();
+
+        assert!(children.iter().all(|&value| value < 0.0));
+        assert_eq!(children.iter().cloned().fold(f64::NEG_INFINITY, f64::max), engine.max_node(blocks, 0));
+
+        let stuck = BitBoard::try_from(&board("2,4,2,4/4,2,4,2/2,4,2,4/4,2,4,2")).unwrap();
+        assert_eq!(LOST, engine.max_node(stuck, 0));
+    }
+
+    #[test]
+    fn test_from_name_rejects_unknown_strategy() {
+        assert!(from_name("oracle", 0, &Settings::default()).is_err());
+    }
+}
@@ -0,0 +1,528 @@
+use crate::block::{self, SpawnRule};
+use crate::board::{Board, Move};
+use crate::cli::Options;
+use crate::tile::Tile;
+use failure::{bail, format_err, Error};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+
+pub const USAGE: &str = "usage: tui-2048 tablebase --output FILE [--width N] [--height N] [--goal N] \
+[--four-probability P]";
+
+const OPTIONS: &[&str] = &["output", "width", "height", "goal", "four-probability", "help"];
+
+/// Largest number of blocks a tablebase can be solved for.
+pub const MAX_BLOCKS: usize = 9;
+
+const MAGIC: &[u8; 6] = b"2048TB";
+const VERSION: u8 = 1;
+
+/// Probabilities are stored as fractions of this value.
+const PROBABILITY_SCALE: f64 = u16::MAX as f64;
+
+/// Packs the exponents of a board in row-major order, 4 bits per block.
+fn pack(board: &Board) -> u64 {
+    board
+        .blocks
+        .iter()
+        .flatten()
+        .enumerate()
+        .fold(0, |key, (i, tile)| key | (tile.rank() as u64) << (4 * i))
+}
+
+fn get(key: u64, cell: usize) -> u8 {
+    ((key >> (4 * cell)) & 0xF) as u8
+}
+
+fn key_bytes(width: usize, height: usize) -> usize {
+    (width * height).div_ceil(2)
+}
+
+/// Orders of the cells of a board shape, so packed keys can be played and
+/// reduced to their canonical symmetry without building boards.
+struct Layout {
+    blocks: usize,
+    /// For each of `Move::ALL`, the cells of every line, starting from the
+    /// edge the blocks slide towards.
+    lines: Vec<Vec<Vec<usize>>>,
+    /// For each symmetry of the board, the cell each cell is taken from.
+    symmetries: Vec<Vec<usize>>,
+}
+
+impl Layout {
+    fn new(width: usize, height: usize) -> Layout {
+        let rows = (0..height)
+            .map(|i| (0..width).map(|j| i * width + j).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let columns = (0..width)
+            .map(|j| (0..height).map(|i| i * width + j).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let reversed = |lines: &Vec<Vec<usize>>| {
+            lines
+                .iter()
+                .map(|line| line.iter().rev().copied().collect())
+                .collect::<Vec<_>>()
+        };
+
+        // number the cells and see where each symmetry takes them
+        let mut cells = Board::new(width, height);
+        for (cell, block) in cells.blocks.iter_mut().flatten().enumerate() {
+            *block = Tile::from(cell as u8);
+        }
+        let symmetries = cells
+            .symmetries()
+            .into_iter()
+            .map(|symmetry| symmetry.ranks().into_iter().flatten().map(usize::from).collect())
+            .collect();
+
+        Layout {
+            blocks: width * height,
+            lines: vec![columns.clone(), reversed(&columns), rows.clone(), reversed(&rows)],
+            symmetries,
+        }
+    }
+
+    fn canonical(&self, key: u64) -> u64 {
+        self.symmetries
+            .iter()
+            .map(|cells| {
+                cells
+                    .iter()
+                    .enumerate()
+                    .fold(0, |next, (cell, &from)| next | (get(key, from) as u64) << (4 * cell))
+            })
+            .min()
+            .unwrap_or(key)
+    }
+
+    /// The key after a move, with the same merge rules as `Board`.
+    fn slide(&self, key: u64, direction: Move) -> u64 {
+        let index = Move::ALL.iter().position(|&other| other == direction).unwrap();
+        let mut next = 0;
+
+        for line in &self.lines[index] {
+            let mut k = 0;
+            let mut merged = false;
+            let mut last = 0;
+
+            for &cell in line {
+                let exponent = get(key, cell);
+                if exponent == 0 {
+                    continue;
+                }
+
+                if k > 0 && last == exponent && !merged && exponent < 15 {
+                    next += 1 << (4 * line[k - 1]);
+                    merged = true;
+                } else {
+                    next |= (exponent as u64) << (4 * line[k]);
+                    last = exponent;
+                    merged = false;
+                    k += 1;
+                }
+            }
+        }
+
+        next
+    }
+
+    fn empty_cells(&self, key: u64) -> Vec<usize> {
+        (0..self.blocks).filter(|&cell| get(key, cell) == 0).collect()
+    }
+
+    fn has_block_with(&self, key: u64, exponent: u8) -> bool {
+        (0..self.blocks).any(|cell| get(key, cell) == exponent)
+    }
+
+    /// Total value of the blocks.
+    fn points(&self, key: u64) -> u64 {
+        (0..self.blocks).map(|cell| block::value(get(key, cell))).sum()
+    }
+}
+
+/// Optimal win probability of every position reachable on a small board,
+/// for one goal and spawn rule. Positions are stored once per symmetry.
+pub struct Tablebase {
+    pub width: usize,
+    pub height: usize,
+    /// Exponent of the block to reach.
+    pub goal: u8,
+    pub spawn_rule: SpawnRule,
+    layout: Layout,
+    /// Canonical positions with the player to move, sorted by key.
+    entries: Vec<(u64, u16)>,
+}
+
+impl Tablebase {
+    fn empty(width: usize, height: usize, goal: u8, spawn_rule: SpawnRule) -> Tablebase {
+        Tablebase {
+            width,
+            height,
+            goal,
+            spawn_rule,
+            layout: Layout::new(width, height),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Enumerates every position reachable from a new game and computes its
+    /// win probability with perfect play, from the positions with the most
+    /// points on the board down, since every move followed by a new block
+    /// adds points.
+    pub fn solve(width: usize, height: usize, goal: u8, spawn_rule: SpawnRule) -> Result<Tablebase, Error> {
+        if width == 0 || height == 0 || width * height > MAX_BLOCKS {
+            bail!("tablebases only cover boards of 1 to {} blocks", MAX_BLOCKS);
+        }
+        if !(2..=15).contains(&goal) {
+            bail!("the goal must be between 4 and 32768");
+        }
+
+        let mut tablebase = Tablebase::empty(width, height, goal, spawn_rule);
+
+        let mut positions = HashSet::new();
+        let mut stack = Vec::new();
+        for (first, _) in tablebase.spawns(0) {
+            for (position, _) in tablebase.spawns(first) {
+                if positions.insert(position) {
+                    stack.push(position);
+                }
+            }
+        }
+        while let Some(key) = stack.pop() {
+            for next in tablebase.moves(key) {
+                if tablebase.layout.has_block_with(next, goal) {
+                    continue;
+                }
+                for (position, _) in tablebase.spawns(next) {
+                    if positions.insert(position) {
+                        stack.push(position);
+                    }
+                }
+            }
+        }
+
+        let mut positions = positions.into_iter().collect::<Vec<u64>>();
+        positions.sort_by_key(|&key| std::cmp::Reverse(tablebase.layout.points(key)));
+
+        let mut values = HashMap::with_capacity(positions.len());
+        for key in positions {
+            let value = tablebase
+                .moves(key)
+                .into_iter()
+                .map(|next| tablebase.after_move(next, |key| values[&key]))
+                .fold(0.0, f64::max);
+
+            values.insert(key, value);
+        }
+
+        tablebase.entries = values
+            .into_iter()
+            .map(|(key, value)| (key, (value * PROBABILITY_SCALE).round() as u16))
+            .collect();
+        tablebase.entries.sort_unstable();
+
+        Ok(tablebase)
+    }
+
+    fn moves(&self, key: u64) -> Vec<u64> {
+        Move::ALL
+            .iter()
+            .map(|&direction| self.layout.slide(key, direction))
+            .filter(|&next| next != key)
+            .collect()
+    }
+
+    /// Canonical keys of the boards a new block can make, with their
+    /// probabilities.
+    fn spawns(&self, key: u64) -> Vec<(u64, f64)> {
+        let empty = self.layout.empty_cells(key);
+        let mut spawns = Vec::new();
+
+        for &cell in &empty {
+            for &(exponent, probability) in &self.spawn_rule.choices() {
+                if probability > 0.0 {
+                    let next = key | (exponent as u64) << (4 * cell);
+
+                    spawns.push((self.layout.canonical(next), probability / empty.len() as f64));
+                }
+            }
+        }
+
+        spawns
+    }
+
+    /// Win probability of a board right after a move, before its new block.
+    fn after_move<F: Fn(u64) -> f64>(&self, key: u64, value: F) -> f64 {
+        if self.layout.has_block_with(key, self.goal) {
+            return 1.0;
+        }
+
+        self.spawns(key)
+            .into_iter()
+            .map(|(key, probability)| probability * value(key))
+            .sum()
+    }
+
+    fn lookup(&self, key: u64) -> Option<f64> {
+        self.entries
+            .binary_search_by_key(&key, |&(entry, _)| entry)
+            .ok()
+            .map(|i| self.entries[i].1 as f64 / PROBABILITY_SCALE)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether the table was solved for boards like `board` and `goal`.
+    pub fn covers(&self, board: &Board, goal: u8, spawn_rule: SpawnRule) -> bool {
+        board.width == self.width
+            && board.height == self.height
+            && goal == self.goal
+            && spawn_rule == self.spawn_rule
+    }
+
+    /// Probability of reaching the goal from `board` with perfect play, or
+    /// `None` when the board cannot be reached in a game.
+    pub fn win_probability(&self, board: &Board) -> Option<f64> {
+        if board.has_block_with(self.goal) {
+            return Some(1.0);
+        }
+
+        self.lookup(self.layout.canonical(pack(board)))
+    }
+
+    /// Win probability of a new game with perfect play.
+    pub fn start_probability(&self) -> f64 {
+        self.spawns(0)
+            .into_iter()
+            .map(|(key, probability)| {
+                probability * self.after_move(key, |key| self.lookup(key).unwrap_or(0.0))
+            })
+            .sum()
+    }
+
+    /// The move with the best win probability, or `None` when stuck.
+    pub fn best_move(&self, board: &Board) -> Option<(Move, f64)> {
+        let key = pack(board);
+
+        Move::ALL
+            .iter()
+            .map(|&direction| (direction, self.layout.slide(key, direction)))
+            .filter(|&(_, next)| next != key)
+            .map(|(direction, next)| {
+                (direction, self.after_move(next, |key| self.lookup(key).unwrap_or(0.0)))
+            })
+            .fold(None, |best, (direction, value)| match best {
+                Some((_, best_value)) if best_value >= value => best,
+                _ => Some((direction, value)),
+            })
+    }
+
+    /// Writes the table as a small header followed by one entry per
+    /// position: its key in `ceil(blocks / 2)` bytes and its probability
+    /// in two bytes, all little endian.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let key_bytes = key_bytes(self.width, self.height);
+        let mut bytes = Vec::with_capacity(32 + self.entries.len() * (key_bytes + 2));
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&[VERSION, self.width as u8, self.height as u8, self.goal]);
+        bytes.extend_from_slice(&self.spawn_rule.four_probability.to_le_bytes());
+        bytes.extend_from_slice(&(self.entries.len() as u64).to_le_bytes());
+        for &(key, value) in &self.entries {
+            bytes.extend_from_slice(&key.to_le_bytes()[..key_bytes]);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        fs::write(path, bytes).map_err(|e| format_err!("cannot write {}: {}", path.display(), e))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Tablebase, Error> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|e| format_err!("cannot read {}: {}", path.display(), e))?;
+
+        Tablebase::from_bytes(&bytes).map_err(|e| format_err!("{}: {}", path.display(), e))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Tablebase, Error> {
+        const HEADER: usize = 26;
+
+        if bytes.len() < HEADER || &bytes[..6] != MAGIC {
+            bail!("not a tablebase");
+        }
+        if bytes[6] != VERSION {
+            bail!("unsupported tablebase version {}", bytes[6]);
+        }
+
+        let (width, height, goal) = (bytes[7] as usize, bytes[8] as usize, bytes[9]);
+        let mut float = [0; 8];
+        float.copy_from_slice(&bytes[10..18]);
+        let mut count = [0; 8];
+        count.copy_from_slice(&bytes[18..26]);
+        let key_bytes = key_bytes(width, height);
+        // the count is untrusted, so a size past `usize` is a corrupt file
+        let size = usize::try_from(u64::from_le_bytes(count))
+            .ok()
+            .and_then(|count| count.checked_mul(key_bytes + 2))
+            .and_then(|entries| entries.checked_add(HEADER));
+        if width * height > MAX_BLOCKS || size != Some(bytes.len()) {
+            bail!("truncated or corrupt tablebase");
+        }
+
+        let entries = bytes[HEADER..]
+            .chunks(key_bytes + 2)
+            .map(|entry| {
+                let mut key = [0; 8];
+                key[..key_bytes].copy_from_slice(&entry[..key_bytes]);
+
+                (
+                    u64::from_le_bytes(key),
+                    u16::from_le_bytes([entry[key_bytes], entry[key_bytes + 1]]),
+                )
+            })
+            .collect();
+
+        let spawn_rule = SpawnRule {
+            four_probability: f64::from_le_bytes(float),
+        };
+
+        Ok(Tablebase {
+            entries,
+            ..Tablebase::empty(width, height, goal, spawn_rule)
+        })
+    }
+}
+
+pub fn main(args: &[String]) -> Result<(), Error> {
+    let options = Options::parse(args, OPTIONS)?;
+
+    if options.flag("help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let output = options
+        .string("output")
+        .ok_or_else(|| format_err!("{}", USAGE))?;
+    let goal: u64 = options.get("goal", 64)?;
+    if !goal.is_power_of_two() {
+        bail!("--goal must be a power of two");
+    }
+    let spawn_rule = SpawnRule {
+        four_probability: options.get("four-probability", SpawnRule::default().four_probability)?,
+    };
+    if !(0.0..=1.0).contains(&spawn_rule.four_probability) {
+        bail!("--four-probability must be between 0 and 1");
+    }
+
+    let tablebase = Tablebase::solve(
+        options.get("width", 3)?,
+        options.get("height", 3)?,
+        block::exponent(goal),
+        spawn_rule,
+    )?;
+    tablebase.save(output)?;
+
+    println!("positions: {}", tablebase.len());
+    println!("win probability of a new game: {:.4}", tablebase.start_probability());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(notation: &str) -> Board {
+        notation.parse().unwrap()
+    }
+
+    #[test]
+    fn test_layout_slide_matches_board() {
+        let layout = Layout::new(3, 2);
+        let blocks = board("2,2,2/4,.,4");
+
+        for &direction in Move::ALL.iter() {
+            assert_eq!(pack(&blocks.try_to_move(direction)), layout.slide(pack(&blocks), direction));
+        }
+    }
+
+    #[test]
+    fn test_layout_canonical_is_shared_by_symmetries() {
+        let layout = Layout::new(3, 3);
+        let blocks = board(".,.,8/.,2,./4,.,.");
+
+        for symmetry in blocks.symmetries() {
+            assert_eq!(layout.canonical(pack(&blocks)), layout.canonical(pack(&symmetry)));
+        }
+        assert_eq!(8, layout.symmetries.len());
+        assert_eq!(2, Layout::new(3, 2).symmetries.len());
+    }
+
+    #[test]
+    fn test_solve_2x2() {
+        let tablebase = Tablebase::solve(2, 2, 4, SpawnRule::default()).unwrap();
+
+        // one move away from a 16
+        assert_eq!(Some(1.0), tablebase.win_probability(&board("8,8/2,.")));
+        assert_eq!(
+            Some((Move::Left, 1.0)),
+            tablebase.best_move(&board("8,8/2,."))
+        );
+        // stuck
+        assert_eq!(Some(0.0), tablebase.win_probability(&board("2,4/4,2")));
+        assert_eq!(None, tablebase.best_move(&board("2,4/4,2")));
+        // symmetric positions share their entry
+        let probability = tablebase.win_probability(&board("2,./.,4")).unwrap();
+        assert!(probability > 0.5 && probability < 1.0);
+        assert_eq!(Some(probability), tablebase.win_probability(&board("4,./.,2")));
+        // unreachable
+        assert_eq!(None, tablebase.win_probability(&board("8,8/.,.")));
+    }
+
+    #[test]
+    fn test_certain_win_with_only_twos() {
+        let tablebase = Tablebase::solve(2, 2, 3, SpawnRule { four_probability: 0.0 }).unwrap();
+
+        assert_eq!(Some(1.0), tablebase.win_probability(&board("2,./.,2")));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let tablebase = Tablebase::solve(2, 3, 4, SpawnRule::default()).unwrap();
+        let path = std::env::temp_dir().join(format!("tui-2048-test-{}.tb", std::process::id()));
+
+        tablebase.save(&path).unwrap();
+        let loaded = Tablebase::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(tablebase.entries, loaded.entries);
+        assert!(loaded.covers(&Board::new(2, 3), 4, SpawnRule::default()));
+        assert!(!loaded.covers(&Board::new(3, 2), 4, SpawnRule::default()));
+    }
+
+    #[test]
+    fn test_load_rejects_counts_past_the_file() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&[VERSION, 2, 2, 3]);
+        bytes.extend_from_slice(&0.1f64.to_le_bytes());
+        // entries of a 2x2 board take 4 bytes, so these would wrap around to
+        // none at all
+        bytes.extend_from_slice(&(u64::MAX / 4 + 1).to_le_bytes());
+
+        assert!(Tablebase::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_solve_rejects_big_boards() {
+        assert!(Tablebase::solve(4, 4, 11, SpawnRule::default()).is_err());
+    }
+}
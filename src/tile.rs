@@ -0,0 +1,178 @@
+use crate::block;
+use crate::merge::MergeRule;
+use failure::{format_err, Error};
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+/// Number of moves a new rock stays on the board.
+pub const ROCK_MOVES: u8 = 10;
+
+/// What a place of a board holds: nothing, a block or one of the special
+/// tiles which play with `--specials`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Tile {
+    #[default]
+    Empty,
+    /// A block of the given rank, see `block`.
+    Block(u8),
+    /// A block which clears the places around it when it merges.
+    Bomb(u8),
+    /// A block worth minus the value of its rank. It cancels out with a block
+    /// of the same rank and merges with negative blocks like a block.
+    Negative(u8),
+    /// Merges with any block as if it were a copy of it, and cancels out
+    /// with negative blocks.
+    Wildcard,
+    /// An obstacle which never moves nor merges and disappears after the
+    /// given number of moves.
+    Rock(u8),
+}
+
+impl From<u8> for Tile {
+    /// The block of rank `rank`, empty for 0.
+    fn from(rank: u8) -> Tile {
+        match rank {
+            0 => Tile::Empty,
+            rank => Tile::Block(rank),
+        }
+    }
+}
+
+impl Tile {
+    pub fn is_empty(self) -> bool {
+        self == Tile::Empty
+    }
+
+    /// Whether the tile is an empty place or a plain block.
+    pub fn is_plain(self) -> bool {
+        matches!(self, Tile::Empty | Tile::Block(_))
+    }
+
+    /// Rank of a block or a bomb, which count towards the goal, and 0 for
+    /// anything else.
+    pub fn rank(self) -> u8 {
+        match self {
+            Tile::Block(rank) | Tile::Bomb(rank) => rank,
+            _ => 0,
+        }
+    }
+
+    /// What the tile becomes when `other` slides into it, if they merge,
+    /// and whether a bomb went off. Tiles which cancel out leave an empty
+    /// place.
+    pub fn merge(self, other: Tile, rule: &dyn MergeRule) -> Option<(Tile, bool)> {
+        let bomb = matches!(self, Tile::Bomb(_)) || matches!(other, Tile::Bomb(_));
+
+        match (self, other) {
+            (Tile::Block(a), Tile::Block(b))
+            | (Tile::Block(a), Tile::Bomb(b))
+            | (Tile::Bomb(a), Tile::Block(b))
+            | (Tile::Bomb(a), Tile::Bomb(b)) => rule.merge(a, b).map(|rank| (Tile::Block(rank), bomb)),
+            (Tile::Wildcard, tile) | (tile, Tile::Wildcard) if tile.rank() > 0 => rule
+                .merge(tile.rank(), tile.rank())
+                .map(|rank| (Tile::Block(rank), bomb)),
+            (Tile::Negative(a), Tile::Negative(b)) => rule.merge(a, b).map(|rank| (Tile::Negative(rank), false)),
+            (Tile::Negative(_), Tile::Wildcard) | (Tile::Wildcard, Tile::Negative(_)) => Some((Tile::Empty, false)),
+            (Tile::Negative(a), tile) | (tile, Tile::Negative(a)) if tile.rank() == a && a > 0 => {
+                Some((Tile::Empty, false))
+            }
+            _ => None,
+        }
+    }
+
+    /// Text shown on the tile: the value of blocks, followed by `!` for bombs
+    /// and preceded by `-` for negative blocks, `*` for wildcards and `#`
+    /// followed by the moves left for rocks.
+    pub fn label(self, rule: &dyn MergeRule) -> String {
+        match self {
+            Tile::Empty => String::new(),
+            Tile::Block(rank) => rule.label(rank),
+            Tile::Bomb(rank) => format!("{}!", rule.label(rank)),
+            Tile::Negative(rank) => format!("-{}", rule.label(rank)),
+            Tile::Wildcard => "*".to_string(),
+            Tile::Rock(moves) => format!("#{}", moves),
+        }
+    }
+
+    /// A special tile, made of a new block of rank `rank` when it needs one.
+    pub fn random_special<R: Rng + ?Sized>(rank: u8, rng: &mut R) -> Tile {
+        match rng.gen_range(0, 4) {
+            0 => Tile::Rock(ROCK_MOVES),
+            1 => Tile::Wildcard,
+            2 => Tile::Bomb(rank),
+            _ => Tile::Negative(rank),
+        }
+    }
+}
+
+/// The tile as written in board notation: `.` when empty, the value of
+/// blocks and special tiles as their label, as in `2`, `4!`, `-2`, `*` and
+/// `#10`.
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Tile::Empty => write!(f, "."),
+            Tile::Block(rank) => write!(f, "{}", block::label(*rank)),
+            Tile::Bomb(rank) => write!(f, "{}!", block::label(*rank)),
+            Tile::Negative(rank) => write!(f, "-{}", block::label(*rank)),
+            Tile::Wildcard => write!(f, "*"),
+            Tile::Rock(moves) => write!(f, "#{}", moves),
+        }
+    }
+}
+
+impl FromStr for Tile {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Tile, Error> {
+        let invalid = || format_err!("invalid block `{}`", text);
+        let rank = |text: &str| block::parse(text).ok_or_else(invalid);
+
+        if text == "." {
+            Ok(Tile::Empty)
+        } else if text == "*" {
+            Ok(Tile::Wildcard)
+        } else if let Some(moves) = text.strip_prefix('#') {
+            moves.parse().map(Tile::Rock).map_err(|_| invalid())
+        } else if let Some(value) = text.strip_prefix('-') {
+            rank(value).map(Tile::Negative)
+        } else if let Some(value) = text.strip_suffix('!') {
+            rank(value).map(Tile::Bomb)
+        } else {
+            rank(text).map(Tile::Block)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::Classic;
+
+    #[test]
+    fn test_merge() {
+        let merge = |a: Tile, b: Tile| a.merge(b, &Classic);
+
+        assert_eq!(Some((Tile::Block(2), false)), merge(Tile::Block(1), Tile::Block(1)));
+        assert_eq!(Some((Tile::Block(4), true)), merge(Tile::Bomb(3), Tile::Block(3)));
+        assert_eq!(Some((Tile::Block(6), false)), merge(Tile::Wildcard, Tile::Block(5)));
+        assert_eq!(Some((Tile::Block(2), true)), merge(Tile::Bomb(1), Tile::Wildcard));
+        assert_eq!(Some((Tile::Empty, false)), merge(Tile::Block(2), Tile::Negative(2)));
+        assert_eq!(Some((Tile::Negative(3), false)), merge(Tile::Negative(2), Tile::Negative(2)));
+        assert_eq!(Some((Tile::Empty, false)), merge(Tile::Negative(4), Tile::Wildcard));
+        assert_eq!(None, merge(Tile::Block(2), Tile::Negative(1)));
+        assert_eq!(None, merge(Tile::Wildcard, Tile::Wildcard));
+        assert_eq!(None, merge(Tile::Rock(3), Tile::Block(1)));
+    }
+
+    #[test]
+    fn test_notation() {
+        for text in &[".", "2", "4!", "-8", "*", "#10"] {
+            assert_eq!(*text, text.parse::<Tile>().unwrap().to_string());
+        }
+        assert_eq!(Tile::Bomb(1), "2!".parse().unwrap());
+        assert!("3!".parse::<Tile>().is_err());
+        assert!("#".parse::<Tile>().is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use crate::board::Move;
+
+/// The shape of a board: which places of its rectangle belong to it and
+/// which place is next to which in every direction.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Topology {
+    /// Rows and columns, moved in the four directions of `Move::ALL`.
+    #[default]
+    Grid,
+    /// A hexagon of hexagonal places with flat tops, moved in the six
+    /// directions of `Move::HEX`. Its rectangle is a square of side
+    /// `2 * radius + 1` holding the hexagon in axial coordinates: the row is
+    /// the position along a column and the places of column `col` are drawn
+    /// half a place lower than those of column `col - 1`.
+    Hex,
+    /// A cube of `height` layers of `height` x `height` places, moved in the
+    /// six directions of `Move::CUBE`. Its rectangle holds the layers side by
+    /// side, front first, so it is `height * height` places wide.
+    Cube,
+    /// A grid whose rows and columns wrap around: the place past the right
+    /// edge is the first one of the row and the place past the bottom edge
+    /// the first one of the column. Blocks still pile up against the edge
+    /// they move to, but the block on it can pass through and merge with the
+    /// block at the other end of its line.
+    Torus,
+}
+
+impl Topology {
+    /// Name of the topology, as given to `--topology` and written in replays.
+    pub fn name(self) -> &'static str {
+        match self {
+            Topology::Grid => "grid",
+            Topology::Hex => "hex",
+            Topology::Cube => "cube",
+            Topology::Torus => "torus",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Topology> {
+        [Topology::Grid, Topology::Hex, Topology::Cube, Topology::Torus]
+            .iter()
+            .copied()
+            .find(|topology| topology.name() == name)
+    }
+
+    /// Directions blocks can be moved in.
+    pub fn directions(self) -> &'static [Move] {
+        match self {
+            Topology::Grid | Topology::Torus => &Move::ALL,
+            Topology::Hex => &Move::HEX,
+            Topology::Cube => &Move::CUBE,
+        }
+    }
+
+    /// Whether the place at `row`, `col` of a `width` x `height` rectangle
+    /// belongs to the board.
+    pub fn contains(self, width: usize, height: usize, row: usize, col: usize) -> bool {
+        if row >= height || col >= width {
+            return false;
+        }
+
+        match self {
+            Topology::Grid | Topology::Cube | Topology::Torus => true,
+            Topology::Hex => {
+                let radius = (width / 2) as isize;
+
+                (row as isize + col as isize - 2 * radius).abs() <= radius
+            }
+        }
+    }
+
+    /// Whether the lines of the board wrap around, see `Topology::Torus`.
+    pub fn wraps(self) -> bool {
+        self == Topology::Torus
+    }
+
+    /// The place next to `row`, `col` towards `direction`, if there is one.
+    /// On a torus, the places on opposite edges are next to each other.
+    pub fn neighbour(
+        self,
+        width: usize,
+        height: usize,
+        start: (usize, usize),
+        direction: Move,
+    ) -> Option<(usize, usize)> {
+        let (row, col) = start;
+        if !self.directions().contains(&direction) {
+            return None;
+        }
+
+        let (dr, dc) = match direction {
+            Move::Up => (-1, 0),
+            Move::Down => (1, 0),
+            Move::Left | Move::UpLeft => (0, -1),
+            Move::Right | Move::DownRight => (0, 1),
+            Move::UpRight => (-1, 1),
+            Move::DownLeft => (1, -1),
+            Move::In => (0, height as isize),
+            Move::Out => (0, -(height as isize)),
+        };
+        let row = row as isize + dr;
+        let next = col as isize + dc;
+
+        // on a cube, left and right stay in the layer
+        let layer = |col: isize| col.div_euclid(height as isize);
+        if self == Topology::Cube && [Move::Left, Move::Right].contains(&direction)
+            && layer(next) != layer(col as isize)
+        {
+            return None;
+        }
+
+        let col = next;
+        if self.wraps() {
+            let place = (
+                row.rem_euclid(height as isize) as usize,
+                col.rem_euclid(width as isize) as usize,
+            );
+
+            // a line of one place does not wrap onto itself
+            return Some(place).filter(|&place| place != start);
+        }
+        if row < 0 || col < 0 {
+            return None;
+        }
+
+        Some((row as usize, col as usize))
+            .filter(|&(row, col)| self.contains(width, height, row, col))
+    }
+
+    /// The lines blocks slide along towards `direction`, each starting at
+    /// the edge they slide to. There are none when the topology does not have
+    /// `direction`. The lines of a torus are those of a grid: they wrap
+    /// around, but blocks slide towards the edge all the same.
+    pub fn lines(self, width: usize, height: usize, direction: Move) -> Vec<Vec<(usize, usize)>> {
+        let opposite = direction.opposite();
+        let mut lines = Vec::new();
+        let edges = if self.wraps() { Topology::Grid } else { self };
+
+        if !self.directions().contains(&direction) {
+            return lines;
+        }
+
+        for row in 0..height {
+            for col in 0..width {
+                let start = (row, col);
+
+                if !self.contains(width, height, row, col)
+                    || edges.neighbour(width, height, start, direction).is_some()
+                {
+                    continue;
+                }
+
+                let mut line = vec![start];
+                while let Some(next) = edges.neighbour(width, height, *line.last().unwrap(), opposite)
+                {
+                    line.push(next);
+                }
+                lines.push(line);
+            }
+        }
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_lines() {
+        let lines = Topology::Grid.lines(3, 2, Move::Right);
+
+        assert_eq!(vec![vec![(0, 2), (0, 1), (0, 0)], vec![(1, 2), (1, 1), (1, 0)]], lines);
+        assert_eq!(3, Topology::Grid.lines(3, 2, Move::Up).len());
+        assert!(Topology::Grid.lines(3, 2, Move::UpLeft).is_empty());
+    }
+
+    #[test]
+    fn test_hex() {
+        let hex = Topology::Hex;
+        let places = (0..5)
+            .flat_map(|row| (0..5).map(move |col| (row, col)))
+            .filter(|&(row, col)| hex.contains(5, 5, row, col))
+            .count();
+
+        // a hexagon of radius 2 has 19 places
+        assert_eq!(19, places);
+        assert!(!hex.contains(5, 5, 0, 0));
+        assert!(hex.contains(5, 5, 0, 2));
+
+        for &direction in Move::HEX.iter() {
+            let lines = hex.lines(5, 5, direction);
+            let lengths = lines.iter().map(Vec::len).collect::<Vec<_>>();
+
+            assert_eq!(19, lengths.iter().sum::<usize>());
+            assert_eq!(5, lines.len());
+            assert!(lengths.contains(&5));
+        }
+    }
+
+    #[test]
+    fn test_cube() {
+        let cube = Topology::Cube;
+
+        assert_eq!(None, cube.neighbour(16, 4, (0, 3), Move::Right));
+        assert_eq!(Some((0, 4)), cube.neighbour(16, 4, (0, 0), Move::In));
+        assert_eq!(None, cube.neighbour(16, 4, (0, 13), Move::In));
+        assert_eq!(Some((1, 9)), cube.neighbour(16, 4, (1, 13), Move::Out));
+
+        for &direction in Move::CUBE.iter() {
+            let lines = cube.lines(16, 4, direction);
+
+            assert_eq!(16, lines.len());
+            assert!(lines.iter().all(|line| line.len() == 4));
+        }
+        assert_eq!(vec![(2, 13), (2, 9), (2, 5), (2, 1)], cube.lines(16, 4, Move::In)[9]);
+    }
+
+    #[test]
+    fn test_torus() {
+        let torus = Topology::Torus;
+
+        assert_eq!(Some((0, 2)), torus.neighbour(3, 2, (0, 0), Move::Left));
+        assert_eq!(Some((0, 1)), torus.neighbour(3, 2, (1, 1), Move::Down));
+        assert_eq!(Some((1, 0)), torus.neighbour(3, 2, (0, 0), Move::Up));
+        assert_eq!(Topology::Grid.lines(3, 2, Move::Right), torus.lines(3, 2, Move::Right));
+    }
+}
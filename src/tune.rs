@@ -0,0 +1,112 @@
+use crate::cli::Options;
+use crate::heuristic::Weights;
+use crate::simulate::{self, Config, Format};
+use crate::spawner;
+use crate::strategy::Settings;
+use failure::{bail, format_err, Error};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::fs;
+
+pub const USAGE: &str = "usage: tui-2048 tune [--iterations N] [--games N] [--threads N] [--seed N] \
+[--depth N] [--step F] [--weights FILE] [--output FILE]";
+
+const OPTIONS: &[&str] = &[
+    "iterations", "games", "threads", "seed", "depth", "step", "weights", "output", "help",
+];
+
+/// Mean score of expectimax self-play with `weights`. Every candidate is
+/// played on the same seeds so the comparison is fair.
+fn evaluate(config: &Config, weights: Weights) -> Result<f64, Error> {
+    let config = Config {
+        settings: Settings::new(config.settings.depth, weights),
+        ..config.clone()
+    };
+
+    Ok(simulate::run(&config)?.mean_score())
+}
+
+/// Hill climbing over the heuristic weights: each iteration scales one
+/// weight up or down by `step` and keeps the change if the mean score of
+/// self-play improves.
+pub fn main(args: &[String]) -> Result<(), Error> {
+    let options = Options::parse(args, OPTIONS)?;
+
+    if options.flag("help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let iterations: usize = options.get("iterations", 20)?;
+    let step: f64 = options.get("step", 0.25)?;
+    // a step of 1 or more would scale weights to zero or flip their sign
+    if !(step > 0.0 && step < 1.0) {
+        bail!("--step must be between 0 and 1");
+    }
+    let seed: u64 = options.get("seed", 0)?;
+    let mut weights = match options.string("weights") {
+        Some(path) => Weights::load(path)?,
+        None => Weights::default(),
+    };
+    let config = Config {
+        strategy: "expectimax".to_string(),
+        games: options.get("games", 20)?,
+        threads: options.get("threads", 1)?.max(1),
+        seed,
+        goal: 2048,
+        width: 4,
+        height: 4,
+        settings: Settings::new(options.get("depth", 2)?, weights),
+        format: Format::Text,
+        spawner: &spawner::Random,
+    };
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let baseline = evaluate(&config, weights)?;
+    let mut best = baseline;
+
+    println!("baseline: {:.1}", baseline);
+
+    for iteration in 1..=iterations {
+        let name = *Weights::NAMES.choose(&mut rng).unwrap();
+        let factor = if rng.gen() { 1.0 + step } else { 1.0 - step };
+        let mut candidate = weights;
+
+        if let Some(weight) = candidate.get_mut(name) {
+            *weight *= factor;
+        }
+
+        let score = evaluate(&config, candidate)?;
+        let accepted = score > best;
+
+        println!(
+            "iteration {}: {} x{:.2} -> {:.1} ({})",
+            iteration,
+            name,
+            factor,
+            score,
+            if accepted { "kept" } else { "dropped" }
+        );
+
+        if accepted {
+            weights = candidate;
+            best = score;
+        }
+    }
+
+    let improvement = if baseline > 0.0 {
+        (best - baseline) / baseline * 100.0
+    } else {
+        0.0
+    };
+    println!("best: {:.1} ({:+.1}% over baseline)", best, improvement);
+    print!("{}", weights);
+
+    if let Some(path) = options.string("output") {
+        fs::write(path, weights.to_string())
+            .map_err(|e| format_err!("cannot write {}: {}", path, e))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use crate::board::Move;
+use crate::game::Game;
+use std::cmp::Ordering;
+use std::time::Duration;
+
+/// How a versus game stands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Playing,
+    /// The player of the given index won.
+    Won(usize),
+    Draw,
+}
+
+/// Two players racing on boards started from the same seed, whose new blocks
+/// have the same values in the same order, see `Game::fair`. The first to win
+/// their game wins; when both games are over otherwise, as when a time limit
+/// runs out, the higher score wins.
+#[derive(Clone)]
+pub struct Versus {
+    pub games: [Game; 2],
+}
+
+impl Versus {
+    pub fn new(game: Game) -> Versus {
+        let game = game.fair();
+
+        Versus {
+            games: [game.clone(), game],
+        }
+    }
+
+    /// Plays `direction` on the board of `player` and returns whether it
+    /// changed it. Nothing moves once the race is over.
+    pub fn play(&mut self, player: usize, direction: Move) -> bool {
+        self.outcome() == Outcome::Playing && self.games[player].play(direction)
+    }
+
+    /// Advances the clocks of both games by `delta`.
+    pub fn tick(&mut self, delta: Duration) {
+        if self.outcome() == Outcome::Playing {
+            for game in self.games.iter_mut() {
+                game.tick(delta);
+            }
+        }
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        let won = [self.games[0].win(), self.games[1].win()];
+        let over = self.games.iter().all(|game| game.win() || game.lose());
+
+        match won {
+            [true, false] => Outcome::Won(0),
+            [false, true] => Outcome::Won(1),
+            _ if over => match self.games[0].score.cmp(&self.games[1].score) {
+                Ordering::Greater => Outcome::Won(0),
+                Ordering::Less => Outcome::Won(1),
+                Ordering::Equal => Outcome::Draw,
+            },
+            _ => Outcome::Playing,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::LoseCondition;
+
+    #[test]
+    fn test_same_values() {
+        let mut versus = Versus::new(Game::with_seed(2048, 4, 4, 3));
+        let mut values = [Vec::new(), Vec::new()];
+        // the players sweep the board in opposite ways
+        let sweeps = [
+            [Move::Left, Move::Up, Move::Right, Move::Down],
+            [Move::Right, Move::Down, Move::Left, Move::Up],
+        ];
+
+        for (player, directions) in sweeps.iter().enumerate() {
+            for _ in 0..20 {
+                for &direction in directions {
+                    if versus.play(player, direction) {
+                        let game = &versus.games[player];
+                        let (row, col) = game.spawned.unwrap();
+
+                        values[player].push(game.board.blocks[row][col].rank());
+                    }
+                }
+            }
+        }
+
+        let length = values[0].len().min(values[1].len());
+        assert!(length > 10);
+        assert_eq!(values[0][..length], values[1][..length]);
+    }
+
+    #[test]
+    fn test_race_to_the_goal() {
+        let position = "2,2,.,./.,.,.,./.,.,.,./.,.,.,.".parse().unwrap();
+        let mut versus = Versus::new(Game::from_position(4, position, 0));
+
+        assert!(versus.play(1, Move::Down));
+        assert_eq!(Outcome::Playing, versus.outcome());
+        assert!(versus.play(0, Move::Left));
+        assert_eq!(Outcome::Won(0), versus.outcome());
+        assert!(!versus.play(1, Move::Left));
+    }
+
+    #[test]
+    fn test_highest_score_when_time_is_up() {
+        let position = "2,2,.,./4,4,.,./.,.,.,./.,.,.,.".parse().unwrap();
+        let game = Game::from_position(2048, position, 0)
+            .losing(LoseCondition::TimeLimit(Duration::from_secs(60)));
+        let mut versus = Versus::new(game);
+
+        versus.play(0, Move::Up);
+        versus.play(1, Move::Left);
+        versus.tick(Duration::from_secs(30));
+        assert_eq!(Outcome::Playing, versus.outcome());
+
+        versus.tick(Duration::from_secs(30));
+        assert_eq!(Outcome::Won(1), versus.outcome());
+        assert_eq!([0, 12], [versus.games[0].score, versus.games[1].score]);
+    }
+}
@@ -0,0 +1,478 @@
+//! Offset of the local time from UTC, read from the time zone database the
+//! way the C library reads it: from the zone `TZ` names when it is set,
+//! otherwise from `/etc/localtime`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const ZONEINFO: &str = "/usr/share/zoneinfo";
+const LOCALTIME: &str = "/etc/localtime";
+
+const DAY: i64 = 86_400;
+const HOUR: i64 = 3_600;
+
+/// Seconds the local time is ahead of UTC at `time`, counted in seconds
+/// since 1970-01-01 UTC. Zero, as in UTC, when no time zone can be read.
+pub fn local_offset(time: i64) -> i64 {
+    local_zone().map_or(0, |zone| zone.offset(time))
+}
+
+fn local_zone() -> Option<Zone> {
+    let name = match env::var("TZ") {
+        Ok(name) if !name.is_empty() => name,
+        _ => return fs::read(LOCALTIME).ok().and_then(|bytes| Zone::parse(&bytes)),
+    };
+    let name = name.trim_start_matches(':');
+    let path = if name.starts_with('/') {
+        PathBuf::from(name)
+    } else {
+        Path::new(ZONEINFO).join(name)
+    };
+
+    // `TZ` is either a zone of the database or a rule such as `CET-1CEST`
+    match fs::read(path) {
+        Ok(bytes) if !name.contains("..") => Zone::parse(&bytes),
+        _ => Rule::parse(name).map(|rule| Zone {
+            transitions: Vec::new(),
+            initial: rule.standard,
+            rule: Some(rule),
+        }),
+    }
+}
+
+/// A time zone as stored in a TZif file, see RFC 8536.
+#[derive(Debug, Clone, PartialEq)]
+struct Zone {
+    /// Times at which the offset changes, in increasing order, each with
+    /// the offset from then on.
+    transitions: Vec<(i64, i64)>,
+    /// Offset before the first transition.
+    initial: i64,
+    /// Offsets after the last transition, when the file has a rule.
+    rule: Option<Rule>,
+}
+
+impl Zone {
+    fn parse(bytes: &[u8]) -> Option<Zone> {
+        let (version, counts) = header(bytes)?;
+        let v1 = &bytes[44..];
+
+        // version 2 repeats the data with 64-bit times, then adds a rule
+        if version == 0 {
+            return Zone::parse_data(v1, counts, 4).map(|(zone, _)| zone);
+        }
+        let v2 = v1.get(data_size(counts, 4)?..)?;
+        let (_, counts) = header(v2)?;
+        let (mut zone, footer) = Zone::parse_data(&v2[44..], counts, 8)?;
+        let footer = std::str::from_utf8(footer).ok()?;
+        zone.rule = footer
+            .strip_prefix('\n')
+            .and_then(|footer| footer.split('\n').next())
+            .and_then(Rule::parse);
+
+        Some(zone)
+    }
+
+    /// Reads the data after a header, returning the zone and what follows.
+    fn parse_data(bytes: &[u8], counts: [usize; 6], time_size: usize) -> Option<(Zone, &[u8])> {
+        let [_, _, _, times, types, _] = counts;
+        let rest = bytes.get(data_size(counts, time_size)?..)?;
+        let offsets = (0..types)
+            .map(|i| {
+                let at = times * (time_size + 1) + 6 * i;
+
+                i64::from(i32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]))
+            })
+            .collect::<Vec<i64>>();
+        let transitions = (0..times)
+            .map(|i| {
+                let time = &bytes[i * time_size..(i + 1) * time_size];
+                let time = if time_size == 4 {
+                    i64::from(i32::from_be_bytes([time[0], time[1], time[2], time[3]]))
+                } else {
+                    let mut bytes = [0; 8];
+                    bytes.copy_from_slice(time);
+                    i64::from_be_bytes(bytes)
+                };
+
+                offsets
+                    .get(bytes[times * time_size + i] as usize)
+                    .map(|&offset| (time, offset))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let zone = Zone {
+            transitions,
+            initial: *offsets.first()?,
+            rule: None,
+        };
+
+        Some((zone, rest))
+    }
+
+    fn offset(&self, time: i64) -> i64 {
+        let last = self.transitions.iter().rposition(|&(at, _)| at <= time);
+
+        match (last, &self.rule) {
+            // the rule holds after the last transition, or always without any
+            (Some(i), Some(rule)) if i + 1 == self.transitions.len() => rule.offset(time),
+            (None, Some(rule)) if self.transitions.is_empty() => rule.offset(time),
+            (Some(i), _) => self.transitions[i].1,
+            (None, _) => self.initial,
+        }
+    }
+}
+
+/// The version, 0 for the first one, and the six counts of a TZif header.
+fn header(bytes: &[u8]) -> Option<(u8, [usize; 6])> {
+    if bytes.len() < 44 || &bytes[..4] != b"TZif" {
+        return None;
+    }
+
+    let mut counts = [0; 6];
+    for (i, count) in counts.iter_mut().enumerate() {
+        let at = 20 + 4 * i;
+
+        *count = u32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as usize;
+    }
+
+    Some((bytes[4].saturating_sub(b'0'), counts))
+}
+
+/// Size of the data after a header of `counts`: transition times and their
+/// types, types, names, leap seconds and indicators.
+fn data_size(counts: [usize; 6], time_size: usize) -> Option<usize> {
+    let [utc, standard, leaps, times, types, names] = counts;
+
+    [
+        times.checked_mul(time_size + 1)?,
+        types.checked_mul(6)?,
+        names,
+        leaps.checked_mul(time_size + 4)?,
+        standard,
+        utc,
+    ]
+    .iter()
+    .try_fold(0usize, |size, &part| size.checked_add(part))
+}
+
+/// A POSIX `TZ` rule such as `CET-1CEST,M3.5.0,M10.5.0/3`: the standard
+/// offset and, for zones with daylight saving time, its offset and when it
+/// starts and ends.
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    standard: i64,
+    daylight: Option<Daylight>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Daylight {
+    offset: i64,
+    /// Day and local time of day at which daylight saving time starts.
+    start: (Day, i64),
+    end: (Day, i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Day {
+    /// `Jn`: day 1 to 365, never counting February 29.
+    Julian(i64),
+    /// `n`: day 0 to 365, counting February 29.
+    Ordinal(i64),
+    /// `Mm.w.d`: weekday `d`, from 0 for Sunday, of week `w` of month `m`,
+    /// where week 5 is the last one.
+    Weekday { month: u32, week: i64, weekday: i64 },
+}
+
+impl Rule {
+    fn parse(text: &str) -> Option<Rule> {
+        let mut text = text;
+        name(&mut text)?;
+        // POSIX offsets count hours west of Greenwich
+        let standard = -offset(&mut text)?;
+        if text.is_empty() {
+            return Some(Rule {
+                standard,
+                daylight: None,
+            });
+        }
+
+        name(&mut text)?;
+        let offset = match text.chars().next() {
+            Some(c) if c.is_ascii_digit() || c == '+' || c == '-' => -offset(&mut text)?,
+            _ => standard + HOUR,
+        };
+        // the rules of the United States when none are given
+        let rules = match text.strip_prefix(',') {
+            Some(rules) => rules,
+            None if text.is_empty() => "M3.2.0,M11.1.0",
+            None => return None,
+        };
+        let mut rules = rules.split(',');
+        let start = transition(rules.next()?)?;
+        let end = transition(rules.next()?)?;
+        if rules.next().is_some() {
+            return None;
+        }
+
+        Some(Rule {
+            standard,
+            daylight: Some(Daylight { offset, start, end }),
+        })
+    }
+
+    fn offset(&self, time: i64) -> i64 {
+        let daylight = match &self.daylight {
+            Some(daylight) => daylight,
+            None => return self.standard,
+        };
+        let year = civil_from_days((time + self.standard).div_euclid(DAY)).0;
+        // daylight saving time starts in standard time and ends in its own
+        let start = daylight.start.0.days(year) * DAY + daylight.start.1 - self.standard;
+        let end = daylight.end.0.days(year) * DAY + daylight.end.1 - daylight.offset;
+        let summer = if start < end {
+            start <= time && time < end
+        } else {
+            !(end <= time && time < start)
+        };
+
+        if summer {
+            daylight.offset
+        } else {
+            self.standard
+        }
+    }
+}
+
+impl Day {
+    /// Days from 1970-01-01 to this day of `year`.
+    fn days(self, year: i32) -> i64 {
+        let new_year = days_from_civil(year, 1, 1);
+
+        match self {
+            Day::Julian(day) => {
+                let leap = days_from_civil(year, 3, 1) - new_year == 60;
+
+                new_year + day - 1 + i64::from(leap && day >= 60)
+            }
+            Day::Ordinal(day) => new_year + day,
+            Day::Weekday {
+                month,
+                week,
+                weekday,
+            } => {
+                let first = days_from_civil(year, month, 1);
+                let next = if month == 12 {
+                    days_from_civil(year + 1, 1, 1)
+                } else {
+                    days_from_civil(year, month + 1, 1)
+                };
+                // 1970-01-01 was a Thursday
+                let mut day = first + (weekday - (first + 4)).rem_euclid(7) + 7 * (week - 1);
+                while day >= next {
+                    day -= 7;
+                }
+
+                day
+            }
+        }
+    }
+}
+
+/// Skips a zone name: letters, or anything between `<` and `>`.
+fn name(text: &mut &str) -> Option<()> {
+    let length = if text.starts_with('<') {
+        text.find('>')? + 1
+    } else {
+        text.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(text.len())
+    };
+    if length < 3 {
+        return None;
+    }
+    *text = &text[length..];
+
+    Some(())
+}
+
+/// Reads `[+-]hh[:mm[:ss]]` as seconds.
+fn offset(text: &mut &str) -> Option<i64> {
+    let sign = match text.chars().next()? {
+        '-' => -1,
+        _ => 1,
+    };
+    let unsigned = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let length = unsigned
+        .find(|c: char| !c.is_ascii_digit() && c != ':')
+        .unwrap_or(unsigned.len());
+    let mut seconds = 0;
+    let mut parts = 0;
+    for part in unsigned[..length].split(':') {
+        seconds = seconds * 60 + part.parse::<i64>().ok()?;
+        parts += 1;
+    }
+    if parts > 3 {
+        return None;
+    }
+    *text = &unsigned[length..];
+
+    Some(sign * seconds * 60_i64.pow(3 - parts))
+}
+
+/// Reads a day and optional `/time` of a daylight saving rule, 02:00 by
+/// default.
+fn transition(text: &str) -> Option<(Day, i64)> {
+    let mut parts = text.splitn(2, '/');
+    let day = parts.next()?;
+    let time = match parts.next() {
+        Some(mut time) => {
+            let seconds = offset(&mut time)?;
+            if !time.is_empty() {
+                return None;
+            }
+
+            seconds
+        }
+        None => 2 * HOUR,
+    };
+    let day = if let Some(day) = day.strip_prefix('J') {
+        Day::Julian(day.parse().ok().filter(|day| (1..=365).contains(day))?)
+    } else if let Some(day) = day.strip_prefix('M') {
+        let fields = day
+            .split('.')
+            .map(str::parse)
+            .collect::<Result<Vec<i64>, _>>()
+            .ok()?;
+
+        match fields[..] {
+            [month, week, weekday]
+                if (1..=12).contains(&month) && (1..=5).contains(&week) && (0..=6).contains(&weekday) =>
+            {
+                Day::Weekday {
+                    month: month as u32,
+                    week,
+                    weekday,
+                }
+            }
+            _ => return None,
+        }
+    } else {
+        Day::Ordinal(day.parse().ok().filter(|day| (0..=365).contains(day))?)
+    };
+
+    Some((day, time))
+}
+
+/// Days from 1970-01-01 to the given date of the proleptic Gregorian
+/// calendar.
+pub fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    // years counted from March, so that leap days end them
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The year, month and day `days` days after 1970-01-01, in the proleptic
+/// Gregorian calendar.
+pub fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    // days and years counted from 0000-03-01, so that leap days end years
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + i64::from(month <= 2);
+
+    (year as i32, month as u32, day as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seconds since the epoch of a UTC date and hour.
+    fn utc(year: i32, month: u32, day: u32, hour: i64) -> i64 {
+        days_from_civil(year, month, day) * DAY + hour * HOUR
+    }
+
+    #[test]
+    fn test_civil_days() {
+        assert_eq!(0, days_from_civil(1970, 1, 1));
+        assert_eq!(20_745, days_from_civil(2026, 10, 19));
+        assert_eq!(-1, days_from_civil(1969, 12, 31));
+
+        for &days in &[-719_468, -1, 0, 11_016, 19_782, 20_745] {
+            let (year, month, day) = civil_from_days(days);
+
+            assert_eq!(days, days_from_civil(year, month, day));
+        }
+    }
+
+    #[test]
+    fn test_rule_without_daylight_saving() {
+        assert_eq!(Some(0), Rule::parse("UTC0").map(|rule| rule.offset(0)));
+        assert_eq!(Some(9 * HOUR), Rule::parse("JST-9").map(|rule| rule.offset(0)));
+        assert_eq!(Some(12_600), Rule::parse("<+0330>-3:30").map(|rule| rule.offset(0)));
+        assert_eq!(None, Rule::parse("5"));
+        assert_eq!(None, Rule::parse("CET-1CEST,M3.5.0"));
+    }
+
+    #[test]
+    fn test_rule_with_daylight_saving() {
+        let new_york = Rule::parse("EST5EDT,M3.2.0,M11.1.0").unwrap();
+
+        assert_eq!(-5 * HOUR, new_york.offset(utc(2026, 1, 15, 12)));
+        assert_eq!(-4 * HOUR, new_york.offset(utc(2026, 7, 1, 12)));
+        // 2026-03-08 02:00 EST and 2026-11-01 02:00 EDT
+        assert_eq!(-5 * HOUR, new_york.offset(utc(2026, 3, 8, 7) - 1));
+        assert_eq!(-4 * HOUR, new_york.offset(utc(2026, 3, 8, 7)));
+        assert_eq!(-4 * HOUR, new_york.offset(utc(2026, 11, 1, 6) - 1));
+        assert_eq!(-5 * HOUR, new_york.offset(utc(2026, 11, 1, 6)));
+        assert_eq!(new_york, Rule::parse("EST5EDT").unwrap());
+
+        let sydney = Rule::parse("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        assert_eq!(11 * HOUR, sydney.offset(utc(2026, 1, 15, 0)));
+        assert_eq!(10 * HOUR, sydney.offset(utc(2026, 7, 1, 0)));
+    }
+
+    #[test]
+    fn test_zone_file() {
+        let mut bytes = Vec::new();
+        let mut block = |version: u8, times: &[i64], time_size: usize| {
+            bytes.extend_from_slice(b"TZif");
+            bytes.push(version);
+            bytes.extend_from_slice(&[0; 15]);
+            for &count in &[0, 0, 0, times.len(), 2, 8] {
+                bytes.extend_from_slice(&(count as u32).to_be_bytes());
+            }
+            for &time in times {
+                bytes.extend_from_slice(&time.to_be_bytes()[8 - time_size..]);
+            }
+            bytes.extend_from_slice(&[1, 0][..times.len()]);
+            bytes.extend_from_slice(&3600i32.to_be_bytes());
+            bytes.extend_from_slice(&[0, 0]);
+            bytes.extend_from_slice(&7200i32.to_be_bytes());
+            bytes.extend_from_slice(&[1, 4]);
+            bytes.extend_from_slice(b"CET\0CEST");
+        };
+        block(b'2', &[1000, 2000], 4);
+        block(b'2', &[1000, 2000], 8);
+        bytes.extend_from_slice(b"\nJST-9\n");
+
+        let zone = Zone::parse(&bytes).unwrap();
+
+        assert_eq!(3600, zone.offset(0));
+        assert_eq!(7200, zone.offset(1000));
+        assert_eq!(7200, zone.offset(1999));
+        assert_eq!(9 * HOUR, zone.offset(2000));
+        assert_eq!(None, Zone::parse(&bytes[..60]));
+    }
+}